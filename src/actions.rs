@@ -0,0 +1,345 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use sysinfo::{Pid, System};
+
+use crate::alerts::Alert;
+use crate::audit::AuditEntry;
+use crate::{ProcessInfo, ResourceMonitor};
+
+// Something a rule does when its alert fires, on top of notifying
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    // Terminates the offending process (process rules only)
+    Kill,
+    // Changes the offending process's priority, -20 (highest) to 19 (lowest)
+    Renice { nice: i32 },
+    // Writes every live process to a JSON snapshot in the directory
+    ExportSnapshot { directory: String },
+    // Turns the snapshot log on for a while
+    StartRecording { minutes: f32 },
+}
+
+impl RuleAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleAction::Kill => "Kill process",
+            RuleAction::Renice { .. } => "Renice process",
+            RuleAction::ExportSnapshot { .. } => "Export snapshot",
+            RuleAction::StartRecording { .. } => "Start recording",
+        }
+    }
+
+    fn needs_process(&self) -> bool {
+        matches!(self, RuleAction::Kill | RuleAction::Renice { .. })
+    }
+}
+
+pub fn describe_process(system: &System, pid: u32) -> String {
+    match system.process(Pid::from_u32(pid)) {
+        Some(process) => format!("{} ({})", process.name(), pid),
+        None => format!("PID {}", pid),
+    }
+}
+
+// Shared by the Terminate button and the kill action
+pub fn kill_process(system: &System, pid: u32) -> Result<String, String> {
+    if pid == std::process::id() {
+        return Err("refusing to kill SysWatch itself".to_string());
+    }
+    let process = system
+        .process(Pid::from_u32(pid))
+        .ok_or_else(|| "process no longer exists".to_string())?;
+    if process.kill() {
+        Ok("killed".to_string())
+    } else {
+        Err("kill failed, check permissions".to_string())
+    }
+}
+
+#[cfg(unix)]
+pub fn renice_process(pid: u32, nice: i32) -> Result<String, String> {
+    if pid == std::process::id() {
+        return Err("refusing to renice SysWatch itself".to_string());
+    }
+    // SAFETY: setpriority only reads its integer arguments
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) };
+    if result == 0 {
+        Ok(format!("nice set to {}", nice))
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+// Windows has priority classes instead of nice values, so map onto the nearest one
+#[cfg(windows)]
+pub fn renice_process(pid: u32, nice: i32) -> Result<String, String> {
+    if pid == std::process::id() {
+        return Err("refusing to renice SysWatch itself".to_string());
+    }
+    let class = match nice {
+        15.. => "Idle",
+        5..=14 => "BelowNormal",
+        -4..=4 => "Normal",
+        -14..=-5 => "AboveNormal",
+        _ => "High",
+    };
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", &format!("(Get-Process -Id {}).PriorityClass = '{}'", pid, class)])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(format!("priority class set to {}", class))
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// Address of the calling thread's errno, on the targets where libc exposes it
+#[cfg(any(target_os = "linux", target_os = "emscripten", target_os = "redox", target_os = "hurd", target_os = "dragonfly"))]
+fn errno_location() -> Option<*mut libc::c_int> {
+    // SAFETY: only returns the address of the thread-local errno
+    Some(unsafe { libc::__errno_location() })
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+fn errno_location() -> Option<*mut libc::c_int> {
+    // SAFETY: only returns the address of the thread-local errno
+    Some(unsafe { libc::__errno() })
+}
+
+#[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+fn errno_location() -> Option<*mut libc::c_int> {
+    // SAFETY: only returns the address of the thread-local errno
+    Some(unsafe { libc::__error() })
+}
+
+#[cfg(all(unix, not(any(
+    target_os = "linux", target_os = "emscripten", target_os = "redox", target_os = "hurd", target_os = "dragonfly",
+    target_os = "android", target_os = "netbsd", target_os = "openbsd",
+    target_vendor = "apple", target_os = "freebsd",
+))))]
+fn errno_location() -> Option<*mut libc::c_int> {
+    None
+}
+
+#[cfg(unix)]
+pub fn process_nice(pid: u32) -> Option<i32> {
+    // getpriority returns -1 both on failure and for nice -1, so errno is cleared first to tell them apart
+    let errno = errno_location();
+    if let Some(errno) = errno {
+        // SAFETY: errno is thread-local and always valid to write
+        unsafe { *errno = 0 };
+    }
+    // SAFETY: getpriority only reads its integer arguments
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+    if nice != -1 {
+        return Some(nice);
+    }
+    // Without errno a -1 can't be told from a failure, so it counts as unknown
+    // SAFETY: as above
+    errno.filter(|&errno| unsafe { *errno } == 0).map(|_| -1)
+}
+
+// Windows has priority classes rather than nice values
+#[cfg(windows)]
+pub fn process_nice(_pid: u32) -> Option<i32> {
+    None
+}
+
+impl ResourceMonitor {
+    // Runs the actions of the rule behind a freshly fired alert
+    pub(crate) fn run_rule_actions(&mut self, alert: &Alert, processes: &[ProcessInfo]) {
+        let Some(rule) = self.alert_config.rules.iter().find(|r| r.id == alert.rule_id) else {
+            return;
+        };
+        if rule.actions.is_empty() {
+            return;
+        }
+        let actions = rule.actions.clone();
+        let dry_run = rule.dry_run;
+        let source = format!("rule: {}", rule.name);
+
+        for action in actions {
+            let target = match (action.needs_process(), alert.pid) {
+                (true, Some(pid)) => describe_process(&self.system, pid),
+                (true, None) => {
+                    self.record_audit(AuditEntry::new(&source, action.label(), &alert.subject, dry_run,
+                        Err("skipped, the rule does not watch a process".to_string())));
+                    continue;
+                }
+                (false, _) => alert.subject.clone(),
+            };
+
+            let result = if dry_run {
+                Ok("not performed".to_string())
+            } else {
+                self.perform_action(&action, alert.pid, processes)
+            };
+            self.record_audit(AuditEntry::new(&source, action.label(), &target, dry_run, result));
+        }
+    }
+
+    fn perform_action(&mut self, action: &RuleAction, pid: Option<u32>, processes: &[ProcessInfo]) -> Result<String, String> {
+        match action {
+            RuleAction::Kill => kill_process(&self.system, pid.unwrap_or_default()),
+            RuleAction::Renice { nice } => renice_process(pid.unwrap_or_default(), *nice),
+            RuleAction::ExportSnapshot { directory } => self
+                .export_full_snapshot(Path::new(directory), processes)
+                .map(|path| format!("saved to {}", path.display()))
+                .map_err(|e| e.to_string()),
+            RuleAction::StartRecording { minutes } => self.start_recording(*minutes),
+        }
+    }
+}
+
+// Editor for one rule's actions, shown inside the rule list
+pub fn render_actions_editor(ui: &mut egui::Ui, actions: &mut Vec<RuleAction>, dry_run: &mut bool) {
+    let mut remove = None;
+
+    ui.horizontal(|ui| {
+        ui.checkbox(dry_run, "Dry run")
+            .on_hover_text("Only record what would have been done in the audit trail");
+        ui.label("Add: ");
+        if ui.small_button("Kill").clicked() {
+            actions.push(RuleAction::Kill);
+        }
+        if ui.small_button("Renice").clicked() {
+            actions.push(RuleAction::Renice { nice: 10 });
+        }
+        if ui.small_button("Export snapshot").clicked() {
+            actions.push(RuleAction::ExportSnapshot { directory: ".".to_string() });
+        }
+        if ui.small_button("Start recording").clicked() {
+            actions.push(RuleAction::StartRecording { minutes: 10.0 });
+        }
+    });
+
+    for (index, action) in actions.iter_mut().enumerate() {
+        ui.push_id(index, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(action.label());
+                match action {
+                    RuleAction::Kill => {}
+                    RuleAction::Renice { nice } => {
+                        ui.add(egui::DragValue::new(nice).clamp_range(-20..=19).prefix("nice "));
+                    }
+                    RuleAction::ExportSnapshot { directory } => {
+                        ui.add(egui::TextEdit::singleline(directory)
+                            .hint_text("directory")
+                            .desired_width(220.0));
+                    }
+                    RuleAction::StartRecording { minutes } => {
+                        ui.add(egui::DragValue::new(minutes)
+                            .clamp_range(1.0..=24.0 * 60.0)
+                            .suffix(" min"));
+                    }
+                }
+                if ui.small_button("Remove").clicked() {
+                    remove = Some(index);
+                }
+            });
+        });
+    }
+
+    if let Some(index) = remove {
+        actions.remove(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertRule;
+
+    fn monitor(actions: Vec<RuleAction>, dry_run: bool) -> ResourceMonitor {
+        let mut monitor = ResourceMonitor::new();
+        monitor.audit_path = None;
+        monitor.alert_config.rules = vec![AlertRule { name: "Runaway".to_string(), actions, dry_run, ..AlertRule::new(1) }];
+        monitor
+    }
+
+    fn alert(subject: &str, pid: Option<u32>) -> Alert {
+        Alert {
+            id: 1,
+            rule_id: 1,
+            rule_name: "Runaway".to_string(),
+            condition: "Process CPU > 90 %".to_string(),
+            subject: subject.to_string(),
+            pid,
+            started_at: 0.0,
+            resolved_at: None,
+            value: 99.0,
+            peak: 99.0,
+            unit: " %".to_string(),
+        }
+    }
+
+    fn outcomes(monitor: &ResourceMonitor) -> Vec<(String, bool, bool, String)> {
+        monitor
+            .audit_log
+            .iter()
+            .map(|e| (e.action.clone(), e.dry_run, e.success, e.outcome.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn dry_run_only_records_the_actions() {
+        // Our own PID: a real kill or renice would be refused and recorded as a failure
+        let pid = std::process::id();
+        let directory = std::env::temp_dir().join(format!("syswatch_dry_run_{}", pid));
+        let mut monitor = monitor(vec![
+            RuleAction::Kill,
+            RuleAction::Renice { nice: 10 },
+            RuleAction::ExportSnapshot { directory: directory.to_string_lossy().into_owned() },
+        ], true);
+
+        monitor.run_rule_actions(&alert("self", Some(pid)), &[]);
+
+        let not_performed = |action: &str| (action.to_string(), true, true, "not performed".to_string());
+        assert_eq!(outcomes(&monitor), [not_performed("Kill process"), not_performed("Renice process"), not_performed("Export snapshot")]);
+        assert!(monitor.audit_log.iter().all(|e| e.source == "rule: Runaway"));
+        assert!(monitor.audit_log[0].target.ends_with(&format!("({})", pid)), "{}", monitor.audit_log[0].target);
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn process_actions_without_a_process_are_skipped() {
+        let directory = std::env::temp_dir().join(format!("syswatch_skipped_{}", std::process::id()));
+        let mut monitor = monitor(vec![
+            RuleAction::Kill,
+            RuleAction::Renice { nice: 10 },
+            RuleAction::ExportSnapshot { directory: directory.to_string_lossy().into_owned() },
+        ], false);
+
+        monitor.run_rule_actions(&alert("", None), &[]);
+        let exported = directory.exists();
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let skipped = |action: &str| (action.to_string(), false, false, "skipped, the rule does not watch a process".to_string());
+        let log = outcomes(&monitor);
+        assert_eq!(log[..2], [skipped("Kill process"), skipped("Renice process")]);
+        // Actions that don't need a process still run
+        assert_eq!((log[2].0.as_str(), log[2].2), ("Export snapshot", true));
+        assert!(exported);
+    }
+
+    #[test]
+    fn rules_without_actions_record_nothing() {
+        let mut monitor = monitor(Vec::new(), false);
+        monitor.run_rule_actions(&alert("", Some(1)), &[]);
+        monitor.run_rule_actions(&Alert { rule_id: 2, ..alert("", Some(1)) }, &[]);
+        assert!(monitor.audit_log.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reads_the_nice_value() {
+        // SAFETY: getpriority only reads its integer arguments
+        let own = unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) };
+        assert_eq!(process_nice(std::process::id()), Some(own));
+        assert_eq!(process_nice(u32::MAX / 2), None);
+    }
+}
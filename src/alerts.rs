@@ -0,0 +1,782 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::actions::{self, RuleAction};
+use crate::notifications::ChannelConfig;
+use crate::theme::color;
+use crate::{format_timestamp, storage, unix_timestamp, DiskInfo, ProcessInfo, ResourceMonitor, SystemTotals};
+
+// File in the config directory holding the alert rules and notification channels
+pub(crate) const ALERTS_FILE: &str = "alerts.json";
+// Resolved alerts kept in the panel
+const MAX_RESOLVED_ALERTS: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMetric {
+    GlobalCpu,
+    MemoryUsage,
+    SwapUsage,
+    DiskUsage,
+    ProcessCpu,
+    ProcessMemory,
+    ProcessCount,
+}
+
+impl RuleMetric {
+    pub const ALL: [RuleMetric; 7] = [
+        RuleMetric::GlobalCpu,
+        RuleMetric::MemoryUsage,
+        RuleMetric::SwapUsage,
+        RuleMetric::DiskUsage,
+        RuleMetric::ProcessCpu,
+        RuleMetric::ProcessMemory,
+        RuleMetric::ProcessCount,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleMetric::GlobalCpu => "Global CPU",
+            RuleMetric::MemoryUsage => "Memory usage",
+            RuleMetric::SwapUsage => "Swap usage",
+            RuleMetric::DiskUsage => "Disk usage",
+            RuleMetric::ProcessCpu => "Process CPU",
+            RuleMetric::ProcessMemory => "Process memory",
+            RuleMetric::ProcessCount => "Process count",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            RuleMetric::ProcessMemory => " MB",
+            RuleMetric::ProcessCount => "",
+            _ => " %",
+        }
+    }
+
+    // What the rule's target field selects, if anything
+    fn target_hint(&self) -> Option<&'static str> {
+        match self {
+            RuleMetric::DiskUsage => Some("mount point, empty for every disk"),
+            RuleMetric::ProcessCpu | RuleMetric::ProcessMemory => Some("process name, empty for any process"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+impl Comparison {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Above => ">",
+            Comparison::Below => "<",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct AlertRule {
+    pub id: u64,
+    pub name: String,
+    pub enabled: bool,
+    pub metric: RuleMetric,
+    // Mount point or process name, depending on the metric
+    #[serde(default)]
+    pub target: String,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    // Distance back past the threshold required before the alert resolves
+    #[serde(default)]
+    pub hysteresis: f64,
+    // How long the condition must hold before the alert fires
+    #[serde(default)]
+    pub duration_secs: f64,
+    // Run when the alert fires; none unless configured
+    #[serde(default)]
+    pub actions: Vec<RuleAction>,
+    // Record the actions in the audit trail without performing them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl AlertRule {
+    pub(crate) fn new(id: u64) -> Self {
+        Self {
+            id,
+            name: format!("Rule {}", id),
+            enabled: true,
+            metric: RuleMetric::GlobalCpu,
+            target: String::new(),
+            comparison: Comparison::Above,
+            threshold: 90.0,
+            hysteresis: 5.0,
+            duration_secs: 0.0,
+            actions: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut text = self.metric.label().to_string();
+        if !self.target.is_empty() {
+            text.push_str(&format!(" [{}]", self.target));
+        }
+        text.push_str(&format!(" {} {}{}", self.comparison.symbol(), self.threshold, self.metric.unit()));
+        if self.duration_secs > 0.0 {
+            text.push_str(&format!(" for {}s", self.duration_secs));
+        }
+        text
+    }
+
+    fn breaches(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => value > self.threshold,
+            Comparison::Below => value < self.threshold,
+        }
+    }
+
+    fn recovered(&self, value: f64) -> bool {
+        match self.comparison {
+            Comparison::Above => value <= self.threshold - self.hysteresis,
+            Comparison::Below => value >= self.threshold + self.hysteresis,
+        }
+    }
+}
+
+fn default_rules() -> Vec<AlertRule> {
+    let root = if cfg!(windows) { "C:\\" } else { "/" };
+    vec![
+        AlertRule {
+            name: "High CPU".to_string(),
+            duration_secs: 120.0,
+            ..AlertRule::new(1)
+        },
+        AlertRule {
+            name: "System disk almost full".to_string(),
+            metric: RuleMetric::DiskUsage,
+            target: root.to_string(),
+            threshold: 85.0,
+            hysteresis: 2.0,
+            ..AlertRule::new(2)
+        },
+        AlertRule {
+            name: "Java memory".to_string(),
+            metric: RuleMetric::ProcessMemory,
+            target: "java".to_string(),
+            threshold: 4096.0,
+            hysteresis: 256.0,
+            ..AlertRule::new(3)
+        },
+        AlertRule {
+            name: "Too many processes".to_string(),
+            metric: RuleMetric::ProcessCount,
+            threshold: 2000.0,
+            hysteresis: 50.0,
+            ..AlertRule::new(4)
+        },
+    ]
+}
+
+// Contents of the alerts file
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub rules: Vec<AlertRule>,
+    pub channels: Vec<ChannelConfig>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            channels: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Alert {
+    pub id: u64,
+    pub rule_id: u64,
+    pub rule_name: String,
+    pub condition: String,
+    // Disk mount point or "name (pid)", empty for machine-wide metrics
+    pub subject: String,
+    // Set for process rules so actions can find the offending process
+    pub pid: Option<u32>,
+    pub started_at: f64,
+    pub resolved_at: Option<f64>,
+    pub value: f64,
+    pub peak: f64,
+    pub unit: String,
+}
+
+impl Alert {
+    pub fn is_active(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+
+    pub fn summary(&self) -> String {
+        let subject = if self.subject.is_empty() { String::new() } else { format!(" on {}", self.subject) };
+        format!("{}{}: {:.1}{} ({})", self.rule_name, subject, self.value, self.unit, self.condition)
+    }
+}
+
+pub enum AlertEvent {
+    Fired(Alert),
+    Resolved(Alert),
+}
+
+// Data one evaluation looks at
+pub struct AlertInput<'a> {
+    pub totals: &'a SystemTotals,
+    pub disks: &'a [DiskInfo],
+    pub processes: &'a [ProcessInfo],
+}
+
+// Per rule and subject tracking between samples
+#[derive(Default)]
+struct SubjectState {
+    breached_since: Option<f64>,
+    active_alert: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct AlertEngine {
+    states: HashMap<(u64, String), SubjectState>,
+    pub alerts: Vec<Alert>,
+    next_alert_id: u64,
+}
+
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 { 0.0 } else { used as f64 / total as f64 * 100.0 }
+}
+
+// Current value of the rule's metric for every subject it applies to
+fn rule_values(rule: &AlertRule, input: &AlertInput) -> Vec<(String, Option<u32>, f64)> {
+    let totals = input.totals;
+    match rule.metric {
+        RuleMetric::GlobalCpu => vec![(String::new(), None, totals.cpu_usage as f64)],
+        RuleMetric::MemoryUsage => vec![(String::new(), None, percent(totals.used_memory, totals.total_memory))],
+        RuleMetric::SwapUsage => vec![(String::new(), None, percent(totals.used_swap, totals.total_swap))],
+        RuleMetric::ProcessCount => vec![(String::new(), None, totals.process_count as f64)],
+        RuleMetric::DiskUsage => input.disks
+            .iter()
+            .filter(|d| rule.target.is_empty() || d.mount_point.eq_ignore_ascii_case(&rule.target))
+            .map(|d| (d.mount_point.clone(), None, d.usage_percent as f64))
+            .collect(),
+        RuleMetric::ProcessCpu | RuleMetric::ProcessMemory => input.processes
+            .iter()
+            .filter(|p| rule.target.is_empty() || process_name_matches(&p.name, &rule.target))
+            .map(|p| {
+                let value = if rule.metric == RuleMetric::ProcessCpu {
+                    p.cpu_usage as f64
+                } else {
+                    p.memory_usage as f64 / 1024.0 / 1024.0
+                };
+                (format!("{} ({})", p.name, p.pid), Some(p.pid), value)
+            })
+            .collect(),
+    }
+}
+
+// Case-insensitive, and "java" also matches "java.exe"
+fn process_name_matches(name: &str, target: &str) -> bool {
+    let name = name.to_lowercase();
+    let target = target.to_lowercase();
+    name == target || name.strip_suffix(".exe") == Some(target.as_str())
+}
+
+impl AlertEngine {
+    pub fn evaluate(&mut self, rules: &[AlertRule], input: &AlertInput, now: f64) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        let mut seen: HashSet<(u64, String)> = HashSet::new();
+
+        for rule in rules.iter().filter(|r| r.enabled) {
+            for (subject, pid, value) in rule_values(rule, input) {
+                let key = (rule.id, subject.clone());
+                seen.insert(key.clone());
+                let state = self.states.entry(key).or_default();
+
+                if rule.breaches(value) {
+                    let since = *state.breached_since.get_or_insert(now);
+                    if state.active_alert.is_none() && now - since >= rule.duration_secs {
+                        self.next_alert_id += 1;
+                        let alert = Alert {
+                            id: self.next_alert_id,
+                            rule_id: rule.id,
+                            rule_name: rule.name.clone(),
+                            condition: rule.describe(),
+                            subject,
+                            pid,
+                            started_at: now,
+                            resolved_at: None,
+                            value,
+                            peak: value,
+                            unit: rule.metric.unit().to_string(),
+                        };
+                        state.active_alert = Some(alert.id);
+                        events.push(AlertEvent::Fired(alert.clone()));
+                        self.alerts.push(alert);
+                    }
+                } else {
+                    state.breached_since = None;
+                }
+
+                let Some(alert_id) = state.active_alert else {
+                    continue;
+                };
+                let resolve = rule.recovered(value);
+                if resolve {
+                    state.active_alert = None;
+                }
+                if let Some(alert) = self.alerts.iter_mut().find(|a| a.id == alert_id) {
+                    alert.value = value;
+                    alert.peak = match rule.comparison {
+                        Comparison::Above => alert.peak.max(value),
+                        Comparison::Below => alert.peak.min(value),
+                    };
+                    if resolve {
+                        alert.resolved_at = Some(now);
+                        events.push(AlertEvent::Resolved(alert.clone()));
+                    }
+                }
+            }
+        }
+
+        // Subjects that vanished (exited process, unmounted disk) or whose rule was removed
+        let stale: Vec<(u64, String)> = self.states
+            .keys()
+            .filter(|key| !seen.contains(key))
+            .cloned()
+            .collect();
+        for key in stale {
+            if let Some(alert_id) = self.states.remove(&key).and_then(|s| s.active_alert) {
+                if let Some(alert) = self.alerts.iter_mut().find(|a| a.id == alert_id) {
+                    alert.resolved_at = Some(now);
+                    events.push(AlertEvent::Resolved(alert.clone()));
+                }
+            }
+        }
+
+        self.trim_resolved();
+        events
+    }
+
+    fn trim_resolved(&mut self) {
+        let resolved = self.alerts.iter().filter(|a| !a.is_active()).count();
+        let mut excess = resolved.saturating_sub(MAX_RESOLVED_ALERTS);
+        self.alerts.retain(|a| {
+            if excess > 0 && !a.is_active() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.alerts.iter().filter(|a| a.is_active()).count()
+    }
+
+    fn clear_resolved(&mut self) {
+        self.alerts.retain(|a| a.is_active());
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AlertsTab {
+    Alerts,
+    Rules,
+    Channels,
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    if seconds >= 3600 {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+impl ResourceMonitor {
+    fn save_alert_config(&mut self) {
+        self.start_notifiers();
+        self.alerts_message = Some(match storage::save_json(ALERTS_FILE, &self.alert_config) {
+            Ok(()) => ("Rules and channels saved".to_string(), true),
+            Err(e) => (format!("Rules and channels applied but not saved: {}", e), false),
+        });
+    }
+
+    // Runs the rules against live data; returns what fired or resolved
+    fn evaluate_alerts(&mut self, processes: &[ProcessInfo]) -> Vec<AlertEvent> {
+        let totals = self.system_totals();
+        let input = AlertInput {
+            totals: &totals,
+            disks: &self.disks,
+            processes,
+        };
+        self.alert_engine.evaluate(&self.alert_config.rules, &input, unix_timestamp())
+    }
+
+    pub(crate) fn check_alerts(&mut self, processes: &[ProcessInfo]) {
+        for event in self.evaluate_alerts(processes) {
+            self.send_notifications(&event);
+            if let AlertEvent::Fired(alert) = &event {
+                self.run_rule_actions(alert, processes);
+            }
+        }
+    }
+
+    pub(crate) fn render_alerts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_alerts_window;
+
+        egui::Window::new("Alerts")
+            .open(&mut open)
+            .default_size([700.0, 420.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let active = self.alert_engine.active_count();
+                    ui.selectable_value(&mut self.alerts_tab, AlertsTab::Alerts, format!("Alerts ({} active)", active));
+                    ui.selectable_value(&mut self.alerts_tab, AlertsTab::Rules,
+                        format!("Rules ({})", self.alert_config.rules.len()));
+                    ui.selectable_value(&mut self.alerts_tab, AlertsTab::Channels,
+                        format!("Channels ({})", self.notifiers.len()));
+                });
+                ui.separator();
+
+                match self.alerts_tab {
+                    AlertsTab::Alerts => self.render_alert_list(ui),
+                    AlertsTab::Rules => self.render_rule_editor(ui),
+                    AlertsTab::Channels => self.render_channel_editor(ui),
+                }
+
+                if self.alerts_tab != AlertsTab::Alerts {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            self.save_alert_config();
+                        }
+                        if let Some((message, success)) = &self.alerts_message {
+                            let color = if *success { color(self.palette.good) } else { ui.visuals().error_fg_color };
+                            ui.label(egui::RichText::new(message).color(color));
+                        }
+                    });
+                }
+            });
+
+        self.show_alerts_window = open;
+        if self.show_alerts_window && self.alerts_tab == AlertsTab::Channels {
+            // Keep the delivery counters moving while the channel list is open
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+
+    fn render_alert_list(&mut self, ui: &mut egui::Ui) {
+        let now = unix_timestamp();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("Active").strong());
+                egui::Grid::new("active_alerts_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for header in ["Rule", "Subject", "Since", "Value", "Peak"] {
+                            ui.label(egui::RichText::new(header).color(ui.visuals().text_color()));
+                        }
+                        ui.end_row();
+
+                        for alert in self.alert_engine.alerts.iter().rev().filter(|a| a.is_active()) {
+                            ui.label(egui::RichText::new(&alert.rule_name).color(color(self.palette.critical)))
+                                .on_hover_text(&alert.condition);
+                            ui.label(&alert.subject);
+                            ui.label(format!("{} ({})",
+                                format_timestamp(alert.started_at, "%H:%M:%S"),
+                                format_duration(now - alert.started_at)));
+                            ui.label(format!("{:.1}{}", alert.value, alert.unit));
+                            ui.label(format!("{:.1}{}", alert.peak, alert.unit));
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Resolved").strong());
+                    if ui.small_button("Clear").clicked() {
+                        self.alert_engine.clear_resolved();
+                    }
+                });
+                egui::Grid::new("resolved_alerts_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for header in ["Rule", "Subject", "Started", "Duration", "Peak"] {
+                            ui.label(egui::RichText::new(header).color(ui.visuals().text_color()));
+                        }
+                        ui.end_row();
+
+                        for alert in self.alert_engine.alerts.iter().rev().filter(|a| !a.is_active()) {
+                            let resolved_at = alert.resolved_at.unwrap_or(now);
+                            ui.label(&alert.rule_name).on_hover_text(&alert.condition);
+                            ui.label(&alert.subject);
+                            ui.label(format_timestamp(alert.started_at, "%Y-%m-%d %H:%M:%S"));
+                            ui.label(format_duration(resolved_at - alert.started_at));
+                            ui.label(format!("{:.1}{}", alert.peak, alert.unit));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn render_rule_editor(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .max_height(ui.available_height() - 70.0)
+            .show(ui, |ui| {
+                for (index, rule) in self.alert_config.rules.iter_mut().enumerate() {
+                    ui.push_id(rule.id, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut rule.enabled, "");
+                            ui.add(egui::TextEdit::singleline(&mut rule.name).desired_width(180.0));
+                            ui.label(egui::RichText::new(rule.describe()).color(ui.visuals().weak_text_color()));
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.small_button("Remove").clicked() {
+                                    remove = Some(index);
+                                }
+                            });
+                        });
+
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_source("metric")
+                                .selected_text(rule.metric.label())
+                                .show_ui(ui, |ui| {
+                                    for metric in RuleMetric::ALL {
+                                        ui.selectable_value(&mut rule.metric, metric, metric.label());
+                                    }
+                                });
+                            if let Some(hint) = rule.metric.target_hint() {
+                                ui.add(egui::TextEdit::singleline(&mut rule.target)
+                                    .hint_text(hint)
+                                    .desired_width(140.0));
+                            }
+                            egui::ComboBox::from_id_source("comparison")
+                                .width(40.0)
+                                .selected_text(rule.comparison.symbol())
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut rule.comparison, Comparison::Above, ">");
+                                    ui.selectable_value(&mut rule.comparison, Comparison::Below, "<");
+                                });
+                            ui.add(egui::DragValue::new(&mut rule.threshold).suffix(rule.metric.unit()));
+                            ui.label("for");
+                            ui.add(egui::DragValue::new(&mut rule.duration_secs)
+                                .clamp_range(0.0..=86_400.0)
+                                .suffix(" s"));
+                            ui.label("hysteresis");
+                            ui.add(egui::DragValue::new(&mut rule.hysteresis)
+                                .clamp_range(0.0..=f64::MAX)
+                                .suffix(rule.metric.unit()))
+                                .on_hover_text("How far the value must move back before the alert resolves");
+                        });
+
+                        let title = if rule.actions.is_empty() {
+                            "Actions".to_string()
+                        } else if rule.dry_run {
+                            format!("Actions ({}, dry run)", rule.actions.len())
+                        } else {
+                            format!("Actions ({})", rule.actions.len())
+                        };
+                        ui.collapsing(title, |ui| {
+                            actions::render_actions_editor(ui, &mut rule.actions, &mut rule.dry_run);
+                        });
+                    });
+                    ui.separator();
+                }
+            });
+
+        if let Some(index) = remove {
+            self.alert_config.rules.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add rule").clicked() {
+                let id = self.alert_config.rules.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+                self.alert_config.rules.push(AlertRule::new(id));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(cpu_usage: f32) -> SystemTotals {
+        SystemTotals {
+            timestamp: 0.0,
+            cpu_usage,
+            total_memory: 0,
+            used_memory: 0,
+            free_memory: 0,
+            total_swap: 0,
+            used_swap: 0,
+            process_count: 0,
+            uptime: 0,
+        }
+    }
+
+    fn process(name: &str, pid: u32, memory_mb: u64) -> ProcessInfo {
+        ProcessInfo { name: name.to_string(), pid, memory_usage: memory_mb * 1024 * 1024, ..Default::default() }
+    }
+
+    fn cpu_rule(threshold: f64, hysteresis: f64, duration_secs: f64) -> AlertRule {
+        AlertRule { threshold, hysteresis, duration_secs, ..AlertRule::new(1) }
+    }
+
+    // Evaluates the rules against a CPU reading and the given processes
+    fn step(engine: &mut AlertEngine, rules: &[AlertRule], cpu: f32, processes: &[ProcessInfo], now: f64) -> Vec<AlertEvent> {
+        let totals = totals(cpu);
+        let input = AlertInput { totals: &totals, disks: &[], processes };
+        engine.evaluate(rules, &input, now)
+    }
+
+    fn fired(events: &[AlertEvent]) -> Vec<&Alert> {
+        events.iter().filter_map(|e| match e { AlertEvent::Fired(a) => Some(a), _ => None }).collect()
+    }
+
+    fn resolved(events: &[AlertEvent]) -> Vec<&Alert> {
+        events.iter().filter_map(|e| match e { AlertEvent::Resolved(a) => Some(a), _ => None }).collect()
+    }
+
+    #[test]
+    fn fires_only_after_the_duration() {
+        let rules = [cpu_rule(90.0, 5.0, 10.0)];
+        let mut engine = AlertEngine::default();
+
+        assert!(step(&mut engine, &rules, 95.0, &[], 100.0).is_empty());
+        assert!(step(&mut engine, &rules, 96.0, &[], 105.0).is_empty());
+        let events = step(&mut engine, &rules, 97.0, &[], 110.0);
+        let alerts = fired(&events);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].started_at, 110.0);
+        assert_eq!(engine.active_count(), 1);
+
+        // Still breaching: no second alert
+        assert!(step(&mut engine, &rules, 99.0, &[], 115.0).is_empty());
+        assert_eq!(engine.alerts[0].peak, 99.0);
+    }
+
+    #[test]
+    fn a_dip_restarts_the_duration() {
+        let rules = [cpu_rule(90.0, 5.0, 10.0)];
+        let mut engine = AlertEngine::default();
+
+        step(&mut engine, &rules, 95.0, &[], 100.0);
+        step(&mut engine, &rules, 50.0, &[], 105.0);
+        assert!(step(&mut engine, &rules, 95.0, &[], 110.0).is_empty());
+        assert!(step(&mut engine, &rules, 95.0, &[], 115.0).is_empty());
+        assert_eq!(fired(&step(&mut engine, &rules, 95.0, &[], 120.0)).len(), 1);
+    }
+
+    #[test]
+    fn resolves_only_past_the_hysteresis() {
+        let rules = [cpu_rule(90.0, 5.0, 0.0)];
+        let mut engine = AlertEngine::default();
+
+        assert_eq!(fired(&step(&mut engine, &rules, 95.0, &[], 0.0)).len(), 1);
+        // Below the threshold but within the hysteresis band
+        assert!(step(&mut engine, &rules, 88.0, &[], 1.0).is_empty());
+        assert!(step(&mut engine, &rules, 85.1, &[], 2.0).is_empty());
+        assert_eq!(engine.active_count(), 1);
+
+        let events = step(&mut engine, &rules, 85.0, &[], 3.0);
+        let alerts = resolved(&events);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].resolved_at, Some(3.0));
+        assert_eq!(alerts[0].peak, 95.0);
+        assert_eq!(engine.active_count(), 0);
+
+        // Breaching again opens a new alert
+        let events = step(&mut engine, &rules, 91.0, &[], 4.0);
+        assert_eq!(fired(&events)[0].id, 2);
+    }
+
+    #[test]
+    fn below_rules_resolve_above_the_threshold() {
+        let rules = [AlertRule { comparison: Comparison::Below, ..cpu_rule(10.0, 2.0, 0.0) }];
+        let mut engine = AlertEngine::default();
+
+        assert_eq!(fired(&step(&mut engine, &rules, 5.0, &[], 0.0)).len(), 1);
+        assert!(step(&mut engine, &rules, 3.0, &[], 1.0).is_empty());
+        assert!(step(&mut engine, &rules, 11.0, &[], 2.0).is_empty());
+        assert_eq!(engine.alerts[0].peak, 3.0);
+        assert_eq!(resolved(&step(&mut engine, &rules, 12.0, &[], 3.0)).len(), 1);
+    }
+
+    #[test]
+    fn vanished_subjects_resolve() {
+        let rules = [AlertRule {
+            metric: RuleMetric::ProcessMemory,
+            target: "java".to_string(),
+            ..cpu_rule(100.0, 10.0, 0.0)
+        }];
+        let mut engine = AlertEngine::default();
+        let running = [process("java", 10, 200), process("java.exe", 11, 300), process("bash", 12, 500)];
+
+        let events = step(&mut engine, &rules, 0.0, &running, 0.0);
+        let mut subjects: Vec<&str> = fired(&events).iter().map(|a| a.subject.as_str()).collect();
+        subjects.sort();
+        assert_eq!(subjects, ["java (10)", "java.exe (11)"]);
+        assert_eq!(fired(&events).iter().find(|a| a.subject == "java (10)").unwrap().pid, Some(10));
+
+        // PID 10 exited
+        let events = step(&mut engine, &rules, 0.0, &running[1..], 1.0);
+        let alerts = resolved(&events);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].subject, "java (10)");
+        assert_eq!(engine.active_count(), 1);
+    }
+
+    #[test]
+    fn removed_and_disabled_rules_resolve() {
+        let mut rules = vec![cpu_rule(90.0, 5.0, 0.0)];
+        let mut engine = AlertEngine::default();
+        step(&mut engine, &rules, 95.0, &[], 0.0);
+
+        rules[0].enabled = false;
+        assert_eq!(resolved(&step(&mut engine, &rules, 95.0, &[], 1.0)).len(), 1);
+        assert_eq!(engine.active_count(), 0);
+    }
+
+    #[test]
+    fn keeps_a_bounded_number_of_resolved_alerts() {
+        let rules = [cpu_rule(90.0, 0.0, 0.0)];
+        let mut engine = AlertEngine::default();
+        for i in 0..(MAX_RESOLVED_ALERTS + 10) {
+            let now = i as f64 * 2.0;
+            step(&mut engine, &rules, 95.0, &[], now);
+            step(&mut engine, &rules, 50.0, &[], now + 1.0);
+        }
+        step(&mut engine, &rules, 95.0, &[], 10_000.0);
+        assert_eq!(engine.alerts.len(), MAX_RESOLVED_ALERTS + 1);
+        assert_eq!(engine.active_count(), 1);
+        // The oldest went first
+        assert_eq!(engine.alerts[0].id, 11);
+    }
+}
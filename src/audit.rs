@@ -0,0 +1,203 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::theme::color;
+use crate::{format_timestamp, unix_timestamp, ResourceMonitor};
+
+// NDJSON file in the config directory every audited action is appended to
+pub(crate) const AUDIT_FILE: &str = "audit.ndjson";
+// Entries kept in memory and shown in the window
+const MAX_AUDIT_ENTRIES: usize = 500;
+// Past this size the file is renamed to audit.1.ndjson, replacing the previous one
+const MAX_AUDIT_FILE_BYTES: u64 = 1024 * 1024;
+
+// One action taken on the system, by hand or by an alert rule
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub timestamp: f64,
+    // "manual" or "rule: <rule name>"
+    pub source: String,
+    pub action: String,
+    pub target: String,
+    pub dry_run: bool,
+    pub success: bool,
+    pub outcome: String,
+}
+
+impl AuditEntry {
+    pub fn new(source: &str, action: &str, target: &str, dry_run: bool, result: Result<String, String>) -> Self {
+        let (success, outcome) = match result {
+            Ok(outcome) => (true, outcome),
+            Err(error) => (false, error),
+        };
+        Self {
+            timestamp: unix_timestamp(),
+            source: source.to_string(),
+            action: action.to_string(),
+            target: target.to_string(),
+            dry_run,
+            success,
+            outcome,
+        }
+    }
+}
+
+// audit.ndjson -> audit.1.ndjson
+fn rotated_path(path: &Path) -> PathBuf {
+    path.with_extension("1.ndjson")
+}
+
+fn append_to_file(path: &Path, entry: &AuditEntry) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // One older generation is kept, so the trail on disk stays bounded
+    if std::fs::metadata(path).is_ok_and(|metadata| metadata.len() >= MAX_AUDIT_FILE_BYTES) {
+        std::fs::rename(path, rotated_path(path))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+// The newest entries of the rotated and the current file, oldest first
+fn read_entries(path: &Path) -> VecDeque<AuditEntry> {
+    let mut entries = VecDeque::new();
+    for file in [rotated_path(path), path.to_path_buf()] {
+        let Ok(contents) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for entry in contents.lines().filter_map(|line| serde_json::from_str(line).ok()) {
+            entries.push_back(entry);
+            if entries.len() > MAX_AUDIT_ENTRIES {
+                entries.pop_front();
+            }
+        }
+    }
+    entries
+}
+
+impl ResourceMonitor {
+    // Loads the most recent entries written by earlier sessions
+    pub(crate) fn load_audit_log(&mut self) {
+        if let Some(path) = &self.audit_path {
+            self.audit_log = read_entries(path);
+        }
+    }
+
+    pub(crate) fn record_audit(&mut self, entry: AuditEntry) {
+        if let Some(path) = &self.audit_path {
+            if let Err(e) = append_to_file(path, &entry) {
+                eprintln!("Error writing audit log: {}", e);
+            }
+        }
+        self.audit_log.push_back(entry);
+        if self.audit_log.len() > MAX_AUDIT_ENTRIES {
+            self.audit_log.pop_front();
+        }
+    }
+
+    pub(crate) fn render_audit_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_audit_window;
+
+        egui::Window::new("Audit trail")
+            .open(&mut open)
+            .default_size([720.0, 360.0])
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Manual terminations and actions triggered by alert rules, newest first")
+                    .color(ui.visuals().weak_text_color()));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        egui::Grid::new("audit_grid")
+                            .num_columns(5)
+                            .striped(true)
+                            .spacing([12.0, 4.0])
+                            .show(ui, |ui| {
+                                for header in ["Time", "Source", "Action", "Target", "Outcome"] {
+                                    ui.label(egui::RichText::new(header).color(ui.visuals().text_color()));
+                                }
+                                ui.end_row();
+
+                                for entry in self.audit_log.iter().rev() {
+                                    ui.label(format_timestamp(entry.timestamp, "%Y-%m-%d %H:%M:%S"));
+                                    ui.label(&entry.source);
+                                    if entry.dry_run {
+                                        ui.label(format!("{} (dry run)", entry.action));
+                                    } else {
+                                        ui.label(&entry.action);
+                                    }
+                                    ui.label(&entry.target);
+                                    let color = if entry.success { color(self.palette.good) } else { ui.visuals().error_fg_color };
+                                    ui.label(egui::RichText::new(&entry.outcome).color(color));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        self.show_audit_window = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(target: &str) -> AuditEntry {
+        AuditEntry::new("manual", "Kill process", target, false, Ok("killed".to_string()))
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syswatch_audit_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn large_files_are_rotated() {
+        let dir = temp_dir("rotate");
+        let path = dir.join(AUDIT_FILE);
+        append_to_file(&path, &entry("first")).unwrap();
+        let line = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, line.repeat(MAX_AUDIT_FILE_BYTES as usize / line.len() + 1)).unwrap();
+
+        append_to_file(&path, &entry("second")).unwrap();
+        let current = std::fs::read_to_string(&path).unwrap();
+        let rotated = std::fs::read_to_string(dir.join("audit.1.ndjson")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(current.lines().count(), 1);
+        assert!(current.contains("\"target\":\"second\""));
+        assert!(rotated.len() as u64 >= MAX_AUDIT_FILE_BYTES);
+    }
+
+    #[test]
+    fn loading_keeps_the_newest_entries_of_both_files() {
+        let dir = temp_dir("load");
+        let path = dir.join(AUDIT_FILE);
+        assert!(read_entries(&path).is_empty());
+
+        std::fs::create_dir_all(&dir).unwrap();
+        let lines = |range: std::ops::Range<usize>| -> String {
+            range.map(|i| serde_json::to_string(&entry(&i.to_string())).unwrap() + "\n").collect()
+        };
+        std::fs::write(dir.join("audit.1.ndjson"), lines(0..400)).unwrap();
+        std::fs::write(&path, lines(400..600) + "not json\n").unwrap();
+
+        let entries = read_entries(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), MAX_AUDIT_ENTRIES);
+        assert_eq!(entries.front().unwrap().target, "100");
+        assert_eq!(entries.back().unwrap().target, "599");
+    }
+}
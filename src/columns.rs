@@ -0,0 +1,441 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::theme::color;
+use crate::{format_timestamp, ProcessInfo, ResourceMonitor};
+
+// Narrowest a column can be dragged to
+pub const MIN_COLUMN_WIDTH: f32 = 40.0;
+// Grab area of the resize handle on a header's right edge
+const RESIZE_HANDLE_WIDTH: f32 = 6.0;
+// Space left of the text in every cell
+pub const CELL_PADDING: f32 = 4.0;
+
+// Columns of the process table
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TableColumn {
+    Name,
+    Pid,
+    ParentPid,
+    Cpu,
+    Memory,
+    VirtualMemory,
+    Threads,
+    Status,
+    User,
+    EffectiveUser,
+    Group,
+    EffectiveGroup,
+    Nice,
+    StartTime,
+    RunTime,
+    DiskRead,
+    DiskWrite,
+    CommandLine,
+    Exe,
+}
+
+impl TableColumn {
+    pub const ALL: [TableColumn; 19] = [
+        TableColumn::Name,
+        TableColumn::Pid,
+        TableColumn::ParentPid,
+        TableColumn::Cpu,
+        TableColumn::Memory,
+        TableColumn::VirtualMemory,
+        TableColumn::Threads,
+        TableColumn::Status,
+        TableColumn::User,
+        TableColumn::EffectiveUser,
+        TableColumn::Group,
+        TableColumn::EffectiveGroup,
+        TableColumn::Nice,
+        TableColumn::StartTime,
+        TableColumn::RunTime,
+        TableColumn::DiskRead,
+        TableColumn::DiskWrite,
+        TableColumn::CommandLine,
+        TableColumn::Exe,
+    ];
+
+    // Shown until the user picks others
+    pub const DEFAULT: [TableColumn; 5] = [
+        TableColumn::Name,
+        TableColumn::Cpu,
+        TableColumn::Memory,
+        TableColumn::Status,
+        TableColumn::User,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TableColumn::Name => "Process Name",
+            TableColumn::Pid => "PID",
+            TableColumn::ParentPid => "PPID",
+            TableColumn::Cpu => "CPU %",
+            TableColumn::Memory => "Memory (MB)",
+            TableColumn::VirtualMemory => "Virtual memory",
+            TableColumn::Threads => "Threads",
+            TableColumn::Status => "Status",
+            TableColumn::User => "User",
+            TableColumn::EffectiveUser => "Effective user",
+            TableColumn::Group => "Group",
+            TableColumn::EffectiveGroup => "Effective group",
+            TableColumn::Nice => "Nice",
+            TableColumn::StartTime => "Started",
+            TableColumn::RunTime => "Run time",
+            TableColumn::DiskRead => "Disk read/s",
+            TableColumn::DiskWrite => "Disk write/s",
+            TableColumn::CommandLine => "Command line",
+            TableColumn::Exe => "Executable",
+        }
+    }
+
+    // Name under [table] in the config file
+    pub fn config_key(&self) -> String {
+        serde_json::to_value(self).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default()
+    }
+
+    pub fn default_width(&self) -> f32 {
+        match self {
+            TableColumn::Name => 260.0,
+            TableColumn::Pid | TableColumn::ParentPid | TableColumn::Threads | TableColumn::Nice => 70.0,
+            TableColumn::Cpu | TableColumn::Status => 100.0,
+            TableColumn::Memory | TableColumn::VirtualMemory => 120.0,
+            TableColumn::User | TableColumn::EffectiveUser | TableColumn::Group | TableColumn::EffectiveGroup => 120.0,
+            TableColumn::DiskRead | TableColumn::DiskWrite | TableColumn::RunTime => 110.0,
+            TableColumn::StartTime => 150.0,
+            TableColumn::CommandLine | TableColumn::Exe => 320.0,
+        }
+    }
+
+    // Direction a first click sorts in: biggest first for amounts, A to Z for the rest
+    pub fn default_descending(&self) -> bool {
+        matches!(
+            self,
+            TableColumn::Cpu
+                | TableColumn::Memory
+                | TableColumn::VirtualMemory
+                | TableColumn::Threads
+                | TableColumn::RunTime
+                | TableColumn::DiskRead
+                | TableColumn::DiskWrite
+        )
+    }
+
+    // Ascending order of two processes by this column; missing values come first
+    pub fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match self {
+            TableColumn::Name => a.name.cmp(&b.name),
+            TableColumn::Pid => a.pid.cmp(&b.pid),
+            TableColumn::ParentPid => a.parent_pid.cmp(&b.parent_pid),
+            TableColumn::Cpu => a.cpu_usage.total_cmp(&b.cpu_usage),
+            TableColumn::Memory => a.memory_usage.cmp(&b.memory_usage),
+            TableColumn::VirtualMemory => a.virtual_memory.cmp(&b.virtual_memory),
+            TableColumn::Threads => a.threads.cmp(&b.threads),
+            TableColumn::Status => a.status.cmp(&b.status),
+            TableColumn::User => a.user.cmp(&b.user),
+            TableColumn::EffectiveUser => a.effective_user.cmp(&b.effective_user),
+            TableColumn::Group => a.group.cmp(&b.group),
+            TableColumn::EffectiveGroup => a.effective_group.cmp(&b.effective_group),
+            TableColumn::Nice => a.nice.cmp(&b.nice),
+            TableColumn::StartTime => a.start_time.cmp(&b.start_time),
+            TableColumn::RunTime => a.run_time.cmp(&b.run_time),
+            TableColumn::DiskRead => a.disk_read_per_sec.total_cmp(&b.disk_read_per_sec),
+            TableColumn::DiskWrite => a.disk_write_per_sec.total_cmp(&b.disk_write_per_sec),
+            TableColumn::CommandLine => a.command_line.cmp(&b.command_line),
+            TableColumn::Exe => a.exe.cmp(&b.exe),
+        }
+    }
+}
+
+// A further sort key, used when the rows are equal on everything before it
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub column: TableColumn,
+    pub descending: bool,
+}
+
+pub fn default_widths() -> BTreeMap<TableColumn, f32> {
+    TableColumn::ALL.iter().map(|&column| (column, column.default_width())).collect()
+}
+
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let time = format!("{:02}:{:02}:{:02}", rest / 3600, rest % 3600 / 60, rest % 60);
+    if days > 0 {
+        format!("{}d {}", days, time)
+    } else {
+        time
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_else(|| "N/A".to_string())
+}
+
+impl ResourceMonitor {
+    // Applies a saved or configured layout; the name column is always kept, and listed once
+    pub(crate) fn set_columns(&mut self, columns: &[TableColumn], widths: &BTreeMap<TableColumn, f32>) {
+        self.visible_columns.clear();
+        for &column in columns {
+            if !self.visible_columns.contains(&column) {
+                self.visible_columns.push(column);
+            }
+        }
+        if !self.visible_columns.contains(&TableColumn::Name) {
+            self.visible_columns.insert(0, TableColumn::Name);
+        }
+        self.column_widths = default_widths();
+        for (&column, &width) in widths {
+            self.column_widths.insert(column, width.max(MIN_COLUMN_WIDTH));
+        }
+    }
+
+    pub(crate) fn column_width(&self, column: TableColumn) -> f32 {
+        self.column_widths.get(&column).copied().unwrap_or_else(|| column.default_width())
+    }
+
+    // Width of all visible columns together
+    pub(crate) fn table_width(&self) -> f32 {
+        self.visible_columns.iter().map(|&c| self.column_width(c)).sum::<f32>() + 2.0 * CELL_PADDING
+    }
+
+    // Text of one cell and the color it is painted in
+    pub(crate) fn cell(&self, column: TableColumn, process: &ProcessInfo) -> (String, egui::Color32) {
+        let text_color = color(self.palette.text);
+        match column {
+            TableColumn::Name => (process.name.clone(), text_color),
+            TableColumn::Pid => (process.pid.to_string(), text_color),
+            TableColumn::ParentPid => (optional(process.parent_pid), text_color),
+            TableColumn::Cpu => (
+                format!("{:.1}%", process.cpu_usage),
+                self.color_thresholds.cpu_color(process.cpu_usage, &self.palette),
+            ),
+            TableColumn::Memory => (
+                format!("{:.1} MB", process.memory_usage as f64 / 1024.0 / 1024.0),
+                self.color_thresholds.memory_color(process.memory_usage, self.system.total_memory(), &self.palette),
+            ),
+            TableColumn::VirtualMemory => (format_bytes(process.virtual_memory as f64), text_color),
+            TableColumn::Threads => (optional(process.threads), text_color),
+            TableColumn::Status => {
+                let status_color = if process.status.contains("Run") {
+                    color(self.palette.good)
+                } else {
+                    color(self.palette.weak_text)
+                };
+                (process.status.clone(), status_color)
+            }
+            TableColumn::User => (optional(process.user.as_deref()), text_color),
+            TableColumn::EffectiveUser => (optional(process.effective_user.as_deref()), text_color),
+            TableColumn::Group => (optional(process.group.as_deref()), text_color),
+            TableColumn::EffectiveGroup => (optional(process.effective_group.as_deref()), text_color),
+            TableColumn::Nice => (optional(process.nice), text_color),
+            TableColumn::StartTime => match process.start_time {
+                0 => ("N/A".to_string(), text_color),
+                start => (format_timestamp(start as f64, "%Y-%m-%d %H:%M:%S"), text_color),
+            },
+            TableColumn::RunTime => (format_duration(process.run_time), text_color),
+            TableColumn::DiskRead => (format!("{}/s", format_bytes(process.disk_read_per_sec)), text_color),
+            TableColumn::DiskWrite => (format!("{}/s", format_bytes(process.disk_write_per_sec)), text_color),
+            TableColumn::CommandLine => (optional(process.command_line.as_deref()), text_color),
+            TableColumn::Exe => (optional(process.exe.as_deref()), text_color),
+        }
+    }
+
+    // Paints the visible cells of a process row, the name shifted right by `indent`
+    pub(crate) fn paint_row_cells(&self, painter: &egui::Painter, rect: egui::Rect, process: &ProcessInfo, font_id: &egui::FontId, indent: f32) {
+        self.paint_cells(painter, rect, font_id, indent, |column| Some(self.cell(column, process)));
+    }
+
+    // Paints whatever `cell` gives for each visible column; text that doesn't fit is cut at the column edge
+    pub(crate) fn paint_cells(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        font_id: &egui::FontId,
+        indent: f32,
+        cell: impl Fn(TableColumn) -> Option<(String, egui::Color32)>,
+    ) {
+        let mut x = rect.min.x + CELL_PADDING;
+        for &column in &self.visible_columns {
+            let width = self.column_width(column);
+            let clip = egui::Rect::from_x_y_ranges(x..=x + width - CELL_PADDING, rect.y_range());
+            if let Some((text, text_color)) = cell(column) {
+                let indent = if column == TableColumn::Name { indent } else { 0.0 };
+                painter.with_clip_rect(clip.intersect(painter.clip_rect())).text(
+                    egui::pos2(x + indent, rect.center().y),
+                    egui::Align2::LEFT_CENTER,
+                    text,
+                    font_id.clone(),
+                    text_color,
+                );
+            }
+            x += width;
+        }
+    }
+
+    // Header row: click to sort, drag to move a column, drag the right edge to resize it,
+    // right-click to choose the columns
+    pub(crate) fn render_table_header(&mut self, ui: &mut egui::Ui) {
+        let height = self.row_height;
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(self.table_width(), height), egui::Sense::hover());
+        let font_id = egui::TextStyle::Body.resolve(ui.style());
+
+        let mut x = rect.min.x + CELL_PADDING;
+        let columns = self.visible_columns.clone();
+        let mut cells = Vec::with_capacity(columns.len());
+        let mut sort = None;
+        let mut drop_target = None;
+
+        for (index, &column) in columns.iter().enumerate() {
+            let width = self.column_width(column);
+            let cell = egui::Rect::from_min_size(egui::pos2(x, rect.min.y), egui::vec2(width, height));
+            cells.push(cell);
+            let id = ui.id().with(("column_header", column));
+            let response = ui.interact(cell.shrink2(egui::vec2(RESIZE_HANDLE_WIDTH / 2.0, 0.0)), id, egui::Sense::click_and_drag());
+
+            let sort_key = self.sort_keys().enumerate().find(|(_, key)| key.column == column);
+            if sort_key.is_some() || response.hovered() {
+                ui.painter().rect_filled(cell, 2.0, ui.visuals().widgets.hovered.weak_bg_fill);
+            }
+            // Arrows show the direction, numbers the order of the keys once there are several
+            let arrow = match sort_key {
+                Some((position, key)) => format!(
+                    " {}{}",
+                    if key.descending { "▼" } else { "▲" },
+                    if self.then_by.is_empty() { String::new() } else { (position + 1).to_string() },
+                ),
+                None => String::new(),
+            };
+            ui.painter().with_clip_rect(cell).text(
+                egui::pos2(cell.min.x + CELL_PADDING, cell.center().y),
+                egui::Align2::LEFT_CENTER,
+                format!("{}{}", column.label(), arrow),
+                font_id.clone(),
+                color(self.palette.text),
+            );
+
+            if response.clicked() {
+                sort = Some((column, ui.input(|i| i.modifiers.shift)));
+            }
+            if response.drag_started() {
+                self.dragged_column = Some(column);
+            }
+            if self.dragged_column == Some(column) && response.drag_stopped() {
+                drop_target = Some(index);
+            }
+            let response = response.on_hover_cursor(if self.dragged_column.is_some() {
+                egui::CursorIcon::Grabbing
+            } else {
+                egui::CursorIcon::PointingHand
+            });
+            response.context_menu(|ui| self.render_column_chooser(ui));
+
+            let handle = egui::Rect::from_center_size(
+                egui::pos2(cell.max.x, cell.center().y),
+                egui::vec2(RESIZE_HANDLE_WIDTH, height),
+            );
+            let handle_response = ui.interact(handle, id.with("resize"), egui::Sense::drag())
+                .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+            if handle_response.dragged() {
+                let new_width = (width + handle_response.drag_delta().x).max(MIN_COLUMN_WIDTH);
+                self.column_widths.insert(column, new_width);
+            }
+            ui.painter().vline(cell.max.x, cell.y_range().shrink(4.0), ui.visuals().widgets.noninteractive.bg_stroke);
+
+            x += width;
+        }
+
+        // Where a dragged column would land: before the column under the pointer, or after the last one
+        let pointer_x = ui.ctx().pointer_interact_pos().map(|p| p.x);
+        let insert_at = pointer_x.map(|px| cells.iter().position(|c| px < c.center().x).unwrap_or(cells.len()));
+        if let (Some(_), Some(at)) = (self.dragged_column, insert_at) {
+            let line_x = cells.get(at).map_or(x, |c| c.min.x);
+            ui.painter().vline(line_x, rect.y_range(), egui::Stroke::new(2.0, color(self.palette.accent)));
+        }
+        if let Some(from) = drop_target {
+            if let Some(mut to) = insert_at {
+                let column = self.visible_columns.remove(from);
+                if to > from {
+                    to -= 1;
+                }
+                self.visible_columns.insert(to.min(self.visible_columns.len()), column);
+            }
+            self.dragged_column = None;
+        }
+        if !ui.input(|i| i.pointer.any_down()) {
+            self.dragged_column = None;
+        }
+
+        if let Some((column, shift)) = sort {
+            self.header_clicked(column, shift);
+        }
+    }
+
+    // A click sorts by the column alone, or flips its direction if it already is the only key.
+    // Shift-click adds the column as a further key, or flips it if it is one already.
+    fn header_clicked(&mut self, column: TableColumn, shift: bool) {
+        if shift && column == self.sort_column {
+            self.sort_descending = !self.sort_descending;
+        } else if shift {
+            match self.then_by.iter_mut().find(|key| key.column == column) {
+                Some(key) => key.descending = !key.descending,
+                None => self.then_by.push(SortKey { column, descending: column.default_descending() }),
+            }
+        } else if column == self.sort_column && self.then_by.is_empty() {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.then_by.clear();
+            self.sort_column = column;
+            self.sort_descending = column.default_descending();
+        }
+        self.sort_process_indices();
+    }
+
+    // Every sort key, the primary one first
+    pub(crate) fn sort_keys(&self) -> impl Iterator<Item = SortKey> + '_ {
+        std::iter::once(SortKey { column: self.sort_column, descending: self.sort_descending })
+            .chain(self.then_by.iter().copied())
+    }
+
+    // Checkbox list of every column; used by the "Columns" menu and the header's context menu
+    pub(crate) fn render_column_chooser(&mut self, ui: &mut egui::Ui) {
+        for column in TableColumn::ALL {
+            let mut shown = self.visible_columns.contains(&column);
+            // The name column is always shown
+            let checkbox = egui::Checkbox::new(&mut shown, column.label());
+            if ui.add_enabled(column != TableColumn::Name, checkbox).changed() {
+                if shown {
+                    self.visible_columns.push(column);
+                } else {
+                    self.visible_columns.retain(|&c| c != column);
+                }
+            }
+        }
+        ui.separator();
+        if ui.button("Reset columns").clicked() {
+            self.visible_columns = TableColumn::DEFAULT.to_vec();
+            self.column_widths = default_widths();
+            ui.close_menu();
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use eframe::egui;
+
+use crate::shortcuts::{parse_shortcut, ShortcutAction, SEARCH_FIELD_ID};
+use crate::theme::ThemeMode;
+use crate::columns::TableColumn;
+use crate::grouping::GroupBy;
+use crate::ResourceMonitor;
+
+// Matches shown in the palette at once
+const MAX_VISIBLE_COMMANDS: usize = 12;
+
+// An action offered by the command palette
+#[derive(Clone, Copy)]
+pub struct Command {
+    pub title: &'static str,
+    // Shortcut shown next to the title, when the action has one
+    pub shortcut: Option<ShortcutAction>,
+    pub run: fn(&mut ResourceMonitor, &egui::Context),
+}
+
+const fn command(title: &'static str, run: fn(&mut ResourceMonitor, &egui::Context)) -> Command {
+    Command { title, shortcut: None, run }
+}
+
+const fn with_shortcut(title: &'static str, shortcut: ShortcutAction, run: fn(&mut ResourceMonitor, &egui::Context)) -> Command {
+    Command { title, shortcut: Some(shortcut), run }
+}
+
+// Every command of the application; new features add theirs here
+pub fn registry() -> Vec<Command> {
+    vec![
+        with_shortcut("Update now", ShortcutAction::Refresh, |m, _| m.request_immediate_update()),
+        command("Toggle system information panel", |m, _| m.show_system_info = !m.show_system_info),
+        command("Toggle disk information panel", |m, _| m.show_disk_info = !m.show_disk_info),
+        command("Toggle users panel", |m, _| m.show_users = !m.show_users),
+        command("Toggle charts panel", |m, _| m.show_charts = !m.show_charts),
+        command("Toggle energy saving", |m, _| m.energy_saving_mode = !m.energy_saving_mode),
+        command("Sort by name", |m, _| m.sort_by(TableColumn::Name, false)),
+        command("Sort by PID", |m, _| m.sort_by(TableColumn::Pid, false)),
+        command("Sort by CPU", |m, _| m.sort_by(TableColumn::Cpu, true)),
+        command("Sort by memory", |m, _| m.sort_by(TableColumn::Memory, true)),
+        command("Sort by status", |m, _| m.sort_by(TableColumn::Status, false)),
+        command("Sort by user", |m, _| m.sort_by(TableColumn::User, false)),
+        command("Sort by start time", |m, _| m.sort_by(TableColumn::StartTime, true)),
+        command("Sort by disk reads", |m, _| m.sort_by(TableColumn::DiskRead, true)),
+        command("Reverse sort order", |m, _| {
+            m.sort_descending = !m.sort_descending;
+            m.sort_process_indices();
+        }),
+        command("Group processes by name", |m, _| m.set_group_by(GroupBy::Name)),
+        command("Group processes by user", |m, _| m.set_group_by(GroupBy::User)),
+        command("Ungroup processes", |m, _| m.set_group_by(GroupBy::None)),
+        command("Expand all groups", |m, _| m.expand_all_groups(true)),
+        command("Collapse all groups", |m, _| m.expand_all_groups(false)),
+        with_shortcut("Search processes", ShortcutAction::FocusSearch, |_, ctx| {
+            ctx.memory_mut(|mem| mem.request_focus(egui::Id::new(SEARCH_FIELD_ID)));
+        }),
+        command("Clear search", |m, _| m.set_filter(String::new())),
+        with_shortcut("Kill selected process", ShortcutAction::Terminate, |m, _| {
+            if m.snapshot.is_none() {
+                m.confirm_kill = m.selected;
+            }
+        }),
+        command("Export...", |m, _| {
+            m.show_export_window = true;
+            m.export_status = None;
+        }),
+        command("Open snapshot...", |m, _| m.open_snapshot()),
+        command("Back to live data", |m, _| {
+            if m.snapshot.is_some() {
+                m.close_snapshot();
+            }
+        }),
+        command("Compare snapshots...", |m, _| m.compare.open = true),
+        command("Toggle Prometheus exporter", |m, _| {
+            if m.metrics_server.is_some() {
+                m.stop_metrics_server();
+            } else {
+                m.start_metrics_server();
+            }
+        }),
+        command("Metric sinks...", |m, _| {
+            m.show_sinks_window = true;
+            m.sinks_message = None;
+        }),
+        command("Snapshot log...", |m, _| m.show_snapshot_log_window = true),
+        command("Alerts...", |m, _| {
+            m.show_alerts_window = true;
+            m.alerts_message = None;
+        }),
+        command("Audit trail...", |m, _| m.show_audit_window = true),
+        command("Color thresholds...", |m, _| m.show_thresholds_window = true),
+        command("Theme...", |m, _| m.show_theme_window = true),
+        command("Theme: follow system", |m, _| m.theme.mode = ThemeMode::System),
+        command("Theme: dark", |m, _| m.theme.mode = ThemeMode::Dark),
+        command("Theme: light", |m, _| m.theme.mode = ThemeMode::Light),
+        command("Theme: high contrast", |m, _| m.theme.mode = ThemeMode::HighContrast),
+        command("Toggle colorblind-safe colors", |m, _| m.theme.colorblind_safe = !m.theme.colorblind_safe),
+        with_shortcut("Keyboard shortcuts...", ShortcutAction::Help, |m, _| m.show_shortcuts_window = true),
+        command("Reset settings", |m, ctx| m.reset_settings(ctx)),
+    ]
+}
+
+// Subsequence match of the query in the title; higher is better, None if some letter is missing.
+// Consecutive letters and letters at word starts score extra, so "tc" ranks "Toggle charts" first.
+fn fuzzy_score(query: &str, title: &str) -> Option<i32> {
+    let title: Vec<char> = title.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + title[position..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous == Some(found.wrapping_sub(1)) {
+            score += 5;
+        }
+        if found == 0 || !title[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    // Prefer shorter titles among equal matches
+    Some(score * 100 - title.len() as i32)
+}
+
+fn matching_commands(query: &str) -> Vec<Command> {
+    let mut matches: Vec<(i32, Command)> = registry()
+        .into_iter()
+        .filter_map(|command| fuzzy_score(query, command.title).map(|score| (score, command)))
+        .collect();
+    if !query.trim().is_empty() {
+        // Stable, so equal scores keep the registry order
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    }
+    matches.into_iter().map(|(_, command)| command).collect()
+}
+
+impl ResourceMonitor {
+    pub(crate) fn open_command_palette(&mut self, ctx: &egui::Context) {
+        self.show_command_palette = true;
+        self.command_palette_opened_frame = ctx.frame_nr();
+        self.command_query.clear();
+        self.command_selected = 0;
+    }
+
+    pub(crate) fn render_command_palette(&mut self, ctx: &egui::Context) {
+        if !self.show_command_palette {
+            return;
+        }
+
+        let matches = matching_commands(&self.command_query);
+        // Navigation keys are taken before the text field sees them
+        let (up, down, enter, escape) = ctx.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        ));
+        let last = matches.len().saturating_sub(1);
+        if up {
+            self.command_selected = self.command_selected.saturating_sub(1);
+        }
+        if down {
+            self.command_selected = (self.command_selected + 1).min(last);
+        }
+        self.command_selected = self.command_selected.min(last);
+
+        let mut chosen = if enter { matches.get(self.command_selected).copied() } else { None };
+        let mut close = escape;
+
+        let response = egui::Window::new("Command palette")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                let edit = ui.add(egui::TextEdit::singleline(&mut self.command_query)
+                    .hint_text("Type a command...")
+                    .desired_width(f32::INFINITY));
+                edit.request_focus();
+                if edit.changed() {
+                    self.command_selected = 0;
+                }
+                ui.separator();
+
+                if matches.is_empty() {
+                    ui.label(egui::RichText::new("No matching command").color(ui.visuals().weak_text_color()));
+                }
+                let first = self.command_selected.saturating_sub(MAX_VISIBLE_COMMANDS - 1);
+                for (index, command) in matches.iter().enumerate().skip(first).take(MAX_VISIBLE_COMMANDS) {
+                    ui.horizontal(|ui| {
+                        let row = ui.add(egui::SelectableLabel::new(index == self.command_selected, command.title));
+                        if row.clicked() {
+                            chosen = Some(*command);
+                        }
+                        if let Some(shortcut) = command.shortcut
+                            .and_then(|action| parse_shortcut(self.key_bindings.get(action)).ok())
+                        {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                ui.label(egui::RichText::new(ctx.format_shortcut(&shortcut))
+                                    .color(ui.visuals().weak_text_color()));
+                            });
+                        }
+                    });
+                }
+            });
+
+        // Clicking elsewhere dismisses the palette, except for the click that opened it
+        if let Some(response) = response {
+            let outside_click = ctx.input(|i| i.pointer.any_click()) && !response.response.contains_pointer();
+            if outside_click && ctx.frame_nr() > self.command_palette_opened_frame && chosen.is_none() {
+                close = true;
+            }
+        }
+
+        if let Some(command) = chosen {
+            self.show_command_palette = false;
+            (command.run)(self, ctx);
+        } else if close {
+            self.show_command_palette = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_or_out_of_order_letters_do_not_match() {
+        assert_eq!(fuzzy_score("tx", "Toggle charts"), None);
+        assert_eq!(fuzzy_score("ba", "abc"), None);
+        assert_eq!(fuzzy_score("a", ""), None);
+    }
+
+    #[test]
+    fn case_and_spaces_in_the_query_are_ignored() {
+        assert_eq!(fuzzy_score("T C", "toggle charts"), fuzzy_score("tc", "Toggle Charts"));
+        assert_eq!(fuzzy_score("   ", "Toggle charts"), fuzzy_score("", "Toggle charts"));
+    }
+
+    #[test]
+    fn word_starts_and_runs_score_higher() {
+        // "tc" hits both word starts in "Toggle charts" but neither in "stack"
+        assert!(fuzzy_score("tc", "Toggle charts") > fuzzy_score("tc", "stack"));
+        // Consecutive letters beat the same letters spread apart
+        assert!(fuzzy_score("ch", "chart") > fuzzy_score("ch", "cash"));
+    }
+
+    #[test]
+    fn ties_go_to_the_shorter_title() {
+        assert!(fuzzy_score("sort", "Sort by CPU") > fuzzy_score("sort", "Sort by memory"));
+        assert_eq!(fuzzy_score("", "abc"), Some(-3));
+    }
+
+    #[test]
+    fn empty_query_keeps_the_registry_order() {
+        let titles = |commands: Vec<Command>| commands.iter().map(|c| c.title).collect::<Vec<_>>();
+        assert_eq!(titles(matching_commands("")), titles(registry()));
+        assert_eq!(titles(matching_commands(" ")), titles(registry()));
+    }
+}
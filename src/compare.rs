@@ -0,0 +1,531 @@
+use eframe::egui;
+use std::collections::HashMap;
+
+use crate::snapshot::Snapshot;
+use crate::theme::{color, Palette};
+use crate::{ProcessInfo, ResourceMonitor};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Appeared,
+    Disappeared,
+    Changed,
+}
+
+impl ChangeKind {
+    fn label(&self) -> &'static str {
+        match self {
+            ChangeKind::Appeared => "Appeared",
+            ChangeKind::Disappeared => "Disappeared",
+            ChangeKind::Changed => "Changed",
+        }
+    }
+
+    fn color(&self, palette: &Palette) -> egui::Color32 {
+        color(match self {
+            ChangeKind::Appeared => palette.good,
+            ChangeKind::Disappeared => palette.critical,
+            ChangeKind::Changed => palette.warning,
+        })
+    }
+}
+
+// One process that differs between the baseline and the target
+pub enum ProcessDiff {
+    Added(ProcessInfo),
+    Removed(ProcessInfo),
+    // The old state is boxed so that a diff is not twice the size of a process
+    Changed { before: Box<ProcessInfo>, after: ProcessInfo },
+}
+
+impl ProcessDiff {
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            ProcessDiff::Added(_) => ChangeKind::Appeared,
+            ProcessDiff::Removed(_) => ChangeKind::Disappeared,
+            ProcessDiff::Changed { .. } => ChangeKind::Changed,
+        }
+    }
+
+    // The newest known state of the process
+    fn process(&self) -> &ProcessInfo {
+        match self {
+            ProcessDiff::Added(process) | ProcessDiff::Removed(process) => process,
+            ProcessDiff::Changed { after, .. } => after,
+        }
+    }
+
+    fn before(&self) -> Option<&ProcessInfo> {
+        match self {
+            ProcessDiff::Added(_) => None,
+            ProcessDiff::Removed(before) => Some(before),
+            ProcessDiff::Changed { before, .. } => Some(before),
+        }
+    }
+
+    fn after(&self) -> Option<&ProcessInfo> {
+        match self {
+            ProcessDiff::Removed(_) => None,
+            ProcessDiff::Added(after) | ProcessDiff::Changed { after, .. } => Some(after),
+        }
+    }
+
+    fn cpu_before(&self) -> f32 {
+        self.before().map_or(0.0, |p| p.cpu_usage)
+    }
+
+    fn cpu_after(&self) -> f32 {
+        self.after().map_or(0.0, |p| p.cpu_usage)
+    }
+
+    pub fn cpu_delta(&self) -> f32 {
+        self.cpu_after() - self.cpu_before()
+    }
+
+    fn memory_before(&self) -> u64 {
+        self.before().map_or(0, |p| p.memory_usage)
+    }
+
+    fn memory_after(&self) -> u64 {
+        self.after().map_or(0, |p| p.memory_usage)
+    }
+
+    pub fn memory_delta(&self) -> i64 {
+        self.memory_after() as i64 - self.memory_before() as i64
+    }
+}
+
+// Smallest thresholds; at 0 every process would count as changed
+const MIN_CPU_THRESHOLD: f32 = 0.1;
+const MIN_MEMORY_THRESHOLD_MB: f32 = 0.1;
+
+// Processes are matched by PID and name, and by start time when both sides have one, so that a
+// reused PID counts as a new process. Appeared and changed processes come in `after` order,
+// disappeared ones in `before` order.
+pub fn diff_processes(
+    before: &[ProcessInfo],
+    after: &[ProcessInfo],
+    cpu_threshold: f32,
+    memory_threshold: u64,
+) -> Vec<ProcessDiff> {
+    let cpu_threshold = cpu_threshold.max(MIN_CPU_THRESHOLD);
+    let memory_threshold = memory_threshold.max((MIN_MEMORY_THRESHOLD_MB * 1024.0 * 1024.0) as u64);
+    let index: HashMap<(u32, &str), usize> = before
+        .iter()
+        .enumerate()
+        .map(|(i, p)| ((p.pid, p.name.as_str()), i))
+        .collect();
+    let mut matched = vec![false; before.len()];
+    let mut diffs = Vec::new();
+
+    for process in after {
+        let old = index
+            .get(&(process.pid, process.name.as_str()))
+            .filter(|&&i| {
+                let start_time = before[i].start_time;
+                // 0 means unknown, e.g. in exports without the start time column
+                start_time == 0 || process.start_time == 0 || start_time == process.start_time
+            });
+        match old {
+            Some(&i) => {
+                matched[i] = true;
+                let diff = ProcessDiff::Changed { before: Box::new(before[i].clone()), after: process.clone() };
+                if diff.cpu_delta().abs() >= cpu_threshold
+                    || diff.memory_delta().unsigned_abs() >= memory_threshold
+                {
+                    diffs.push(diff);
+                }
+            }
+            None => diffs.push(ProcessDiff::Added(process.clone())),
+        }
+    }
+
+    diffs.extend(
+        before
+            .iter()
+            .zip(&matched)
+            .filter(|(_, &matched)| !matched)
+            .map(|(old, _)| ProcessDiff::Removed(old.clone())),
+    );
+
+    diffs
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiffSortColumn {
+    Kind,
+    Name,
+    Pid,
+    CpuDelta,
+    MemoryDelta,
+}
+
+// Where the "after" side of the comparison comes from
+#[derive(Clone, Copy, PartialEq)]
+pub enum CompareTarget {
+    Live,
+    File,
+}
+
+pub struct CompareView {
+    pub open: bool,
+    baseline: Option<Snapshot>,
+    target: CompareTarget,
+    target_file: Option<Snapshot>,
+    cpu_threshold: f32,
+    memory_threshold_mb: f32,
+    show_appeared: bool,
+    show_disappeared: bool,
+    show_changed: bool,
+    sort_column: DiffSortColumn,
+    sort_descending: bool,
+    diffs: Vec<ProcessDiff>,
+    compared_at: Option<String>,
+    error: Option<String>,
+}
+
+impl Default for CompareView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            baseline: None,
+            target: CompareTarget::Live,
+            target_file: None,
+            cpu_threshold: 5.0,
+            memory_threshold_mb: 50.0,
+            show_appeared: true,
+            show_disappeared: true,
+            show_changed: true,
+            sort_column: DiffSortColumn::MemoryDelta,
+            sort_descending: true,
+            diffs: Vec::new(),
+            compared_at: None,
+            error: None,
+        }
+    }
+}
+
+impl CompareView {
+    fn pick_snapshot(&mut self) -> Option<Snapshot> {
+        let path = rfd::FileDialog::new()
+            .add_filter("Snapshot", &["json", "csv"])
+            .pick_file()?;
+        match Snapshot::load(&path) {
+            Ok(snapshot) => {
+                self.error = None;
+                Some(snapshot)
+            }
+            Err(e) => {
+                eprintln!("Error reading snapshot {}: {}", path.display(), e);
+                self.error = Some(format!("Could not open {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    fn sort_diffs(&mut self) {
+        let column = self.sort_column;
+        self.diffs.sort_by(|a, b| {
+            let cmp = match column {
+                DiffSortColumn::Kind => (a.kind() as u8).cmp(&(b.kind() as u8)),
+                DiffSortColumn::Name => a.process().name.cmp(&b.process().name),
+                DiffSortColumn::Pid => a.process().pid.cmp(&b.process().pid),
+                DiffSortColumn::CpuDelta => a.cpu_delta()
+                    .partial_cmp(&b.cpu_delta())
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                DiffSortColumn::MemoryDelta => a.memory_delta().cmp(&b.memory_delta()),
+            };
+            if self.sort_descending { cmp.reverse() } else { cmp }
+        });
+    }
+
+    fn is_visible(&self, diff: &ProcessDiff) -> bool {
+        match diff.kind() {
+            ChangeKind::Appeared => self.show_appeared,
+            ChangeKind::Disappeared => self.show_disappeared,
+            ChangeKind::Changed => self.show_changed,
+        }
+    }
+
+    fn sort_header(&mut self, ui: &mut egui::Ui, label: &str, column: DiffSortColumn) {
+        let text = egui::RichText::new(label).color(ui.visuals().text_color());
+        if ui.selectable_label(self.sort_column == column, text).clicked() {
+            if self.sort_column == column {
+                self.sort_descending = !self.sort_descending;
+            } else {
+                self.sort_column = column;
+                self.sort_descending = matches!(column, DiffSortColumn::CpuDelta | DiffSortColumn::MemoryDelta);
+            }
+            self.sort_diffs();
+        }
+    }
+}
+
+fn format_memory_delta(delta: i64) -> String {
+    format!("{:+.1} MB", delta as f64 / 1024.0 / 1024.0)
+}
+
+impl ResourceMonitor {
+    fn run_comparison(&mut self) {
+        let Some(baseline) = &self.compare.baseline else {
+            return;
+        };
+        let live;
+        let after: &[ProcessInfo] = match self.compare.target {
+            CompareTarget::Live => {
+                live = self.all_processes();
+                &live
+            }
+            CompareTarget::File => match &self.compare.target_file {
+                Some(snapshot) => &snapshot.processes,
+                None => return,
+            },
+        };
+
+        let memory_threshold = (self.compare.memory_threshold_mb as f64 * 1024.0 * 1024.0) as u64;
+        self.compare.diffs = diff_processes(&baseline.processes, after, self.compare.cpu_threshold, memory_threshold);
+        self.compare.compared_at = Some(chrono::Local::now().format("%H:%M:%S").to_string());
+        self.compare.sort_diffs();
+    }
+
+    pub(crate) fn render_compare_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.compare.open;
+        let mut run = false;
+
+        egui::Window::new("Compare snapshots")
+            .open(&mut open)
+            .default_size([760.0, 480.0])
+            .show(ctx, |ui| {
+                let view = &mut self.compare;
+
+                egui::Grid::new("compare_sources")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Before: ");
+                        ui.horizontal(|ui| {
+                            let name = view.baseline.as_ref().map(|s| s.file_name());
+                            ui.label(name.unwrap_or_else(|| "no file".to_string()));
+                            if ui.button("Choose file...").clicked() {
+                                if let Some(snapshot) = view.pick_snapshot() {
+                                    view.baseline = Some(snapshot);
+                                    run = true;
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("After: ");
+                        ui.horizontal(|ui| {
+                            run |= ui.radio_value(&mut view.target, CompareTarget::Live, "Live data").clicked();
+                            run |= ui.radio_value(&mut view.target, CompareTarget::File, "File").clicked();
+                            if view.target == CompareTarget::File {
+                                let name = view.target_file.as_ref().map(|s| s.file_name());
+                                ui.label(name.unwrap_or_else(|| "no file".to_string()));
+                                if ui.button("Choose file...").clicked() {
+                                    if let Some(snapshot) = view.pick_snapshot() {
+                                        view.target_file = Some(snapshot);
+                                        run = true;
+                                    }
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Changed if: ");
+                        ui.horizontal(|ui| {
+                            ui.label("CPU Δ ≥");
+                            run |= ui.add(egui::DragValue::new(&mut view.cpu_threshold)
+                                .clamp_range(MIN_CPU_THRESHOLD..=100.0)
+                                .suffix(" %"))
+                                .changed();
+                            ui.label("or memory Δ ≥");
+                            run |= ui.add(egui::DragValue::new(&mut view.memory_threshold_mb)
+                                .clamp_range(MIN_MEMORY_THRESHOLD_MB..=1_000_000.0)
+                                .suffix(" MB"))
+                                .changed();
+                        });
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut view.show_appeared, "Appeared");
+                    ui.checkbox(&mut view.show_disappeared, "Disappeared");
+                    ui.checkbox(&mut view.show_changed, "Changed");
+                    ui.separator();
+                    if ui.add_enabled(view.baseline.is_some(), egui::Button::new("Compare")).clicked() {
+                        run = true;
+                    }
+                    if let Some(time) = &view.compared_at {
+                        ui.label(egui::RichText::new(format!("Compared at {}", time))
+                            .color(ui.visuals().weak_text_color()));
+                    }
+                });
+
+                if let Some(error) = &view.error {
+                    ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::both()
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        egui::Grid::new("compare_grid")
+                            .num_columns(9)
+                            .striped(true)
+                            .spacing([12.0, 4.0])
+                            .show(ui, |ui| {
+                                view.sort_header(ui, "Change", DiffSortColumn::Kind);
+                                view.sort_header(ui, "Process Name", DiffSortColumn::Name);
+                                view.sort_header(ui, "PID", DiffSortColumn::Pid);
+                                ui.label("CPU before");
+                                ui.label("CPU after");
+                                view.sort_header(ui, "CPU Δ", DiffSortColumn::CpuDelta);
+                                ui.label("Memory before");
+                                ui.label("Memory after");
+                                view.sort_header(ui, "Memory Δ", DiffSortColumn::MemoryDelta);
+                                ui.end_row();
+
+                                for diff in view.diffs.iter().filter(|d| view.is_visible(d)) {
+                                    let process = diff.process();
+                                    ui.label(egui::RichText::new(diff.kind().label()).color(diff.kind().color(&self.palette)));
+                                    ui.label(&process.name);
+                                    ui.label(process.pid.to_string());
+                                    ui.label(format!("{:.1}%", diff.cpu_before()));
+                                    ui.label(format!("{:.1}%", diff.cpu_after()));
+                                    ui.label(format!("{:+.1}%", diff.cpu_delta()));
+                                    ui.label(format!("{:.1} MB", diff.memory_before() as f64 / 1024.0 / 1024.0));
+                                    ui.label(format!("{:.1} MB", diff.memory_after() as f64 / 1024.0 / 1024.0));
+                                    ui.label(format_memory_delta(diff.memory_delta()));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        self.compare.open = open;
+        if run {
+            self.run_comparison();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn process(name: &str, pid: u32, cpu_usage: f32, memory_mb: u64) -> ProcessInfo {
+        ProcessInfo { name: name.to_string(), pid, cpu_usage, memory_usage: memory_mb * MB, ..Default::default() }
+    }
+
+    // (kind, name, pid) of each diff
+    fn summary(diffs: &[ProcessDiff]) -> Vec<(&'static str, String, u32)> {
+        diffs
+            .iter()
+            .map(|d| (d.kind().label(), d.process().name.clone(), d.process().pid))
+            .collect()
+    }
+
+    fn started(process: ProcessInfo, start_time: u64) -> ProcessInfo {
+        ProcessInfo { start_time, ..process }
+    }
+
+    #[test]
+    fn identical_lists_have_no_diffs() {
+        let processes = [process("bash", 1, 1.0, 10), process("sshd", 2, 0.0, 5)];
+        assert!(diff_processes(&processes, &processes, 0.1, 1).is_empty());
+        assert!(diff_processes(&[], &[], 0.0, 0).is_empty());
+    }
+
+    #[test]
+    fn appeared_and_disappeared() {
+        let before = [process("bash", 1, 1.0, 10), process("cron", 2, 0.0, 5)];
+        let after = [process("bash", 1, 1.0, 10), process("vim", 3, 2.0, 20)];
+        let diffs = diff_processes(&before, &after, 5.0, 50 * MB);
+        assert_eq!(summary(&diffs), [
+            ("Appeared", "vim".to_string(), 3),
+            ("Disappeared", "cron".to_string(), 2),
+        ]);
+
+        let vim = diffs.iter().find(|d| d.kind() == ChangeKind::Appeared).unwrap();
+        assert_eq!(vim.cpu_delta(), 2.0);
+        assert_eq!(vim.memory_delta(), 20 * MB as i64);
+        let cron = diffs.iter().find(|d| d.kind() == ChangeKind::Disappeared).unwrap();
+        assert_eq!(cron.memory_delta(), -(5 * MB as i64));
+        assert!(cron.after().is_none());
+    }
+
+    #[test]
+    fn changes_count_from_either_threshold() {
+        let before = [process("a", 1, 10.0, 100), process("b", 2, 10.0, 100), process("c", 3, 10.0, 100)];
+        let after = [process("a", 1, 14.9, 149), process("b", 2, 5.0, 100), process("c", 3, 10.0, 40)];
+        let diffs = diff_processes(&before, &after, 5.0, 50 * MB);
+        assert_eq!(summary(&diffs), [
+            ("Changed", "b".to_string(), 2),
+            ("Changed", "c".to_string(), 3),
+        ]);
+
+        let b = diffs.iter().find(|d| d.process().pid == 2).unwrap();
+        assert_eq!((b.cpu_before(), b.cpu_after(), b.cpu_delta()), (10.0, 5.0, -5.0));
+        let c = diffs.iter().find(|d| d.process().pid == 3).unwrap();
+        assert_eq!(c.memory_delta(), -(60 * MB as i64));
+    }
+
+    #[test]
+    fn a_reused_pid_is_a_new_process() {
+        let before = [process("postgres", 100, 1.0, 300)];
+        let after = [process("nginx", 100, 1.0, 300)];
+        let diffs = diff_processes(&before, &after, 5.0, 50 * MB);
+        assert_eq!(summary(&diffs), [
+            ("Appeared", "nginx".to_string(), 100),
+            ("Disappeared", "postgres".to_string(), 100),
+        ]);
+    }
+
+    #[test]
+    fn same_name_under_a_new_pid_is_a_new_process() {
+        let before = [process("worker", 10, 1.0, 10)];
+        let after = [process("worker", 11, 1.0, 10)];
+        let diffs = diff_processes(&before, &after, 5.0, 50 * MB);
+        assert_eq!(summary(&diffs), [
+            ("Appeared", "worker".to_string(), 11),
+            ("Disappeared", "worker".to_string(), 10),
+        ]);
+    }
+
+    #[test]
+    fn zero_thresholds_ignore_unchanged_processes() {
+        let before = [process("bash", 1, 1.0, 10), process("top", 2, 3.0, 10)];
+        let after = [process("bash", 1, 1.0, 10), process("top", 2, 3.5, 10)];
+        assert_eq!(summary(&diff_processes(&before, &after, 0.0, 0)), [("Changed", "top".to_string(), 2)]);
+    }
+
+    #[test]
+    fn a_restart_on_a_reused_pid_is_a_new_process() {
+        let before = [started(process("worker", 10, 1.0, 10), 1000), started(process("cron", 11, 0.0, 5), 900)];
+        let after = [started(process("worker", 10, 1.0, 10), 2000), started(process("cron", 11, 0.0, 5), 900)];
+        assert_eq!(summary(&diff_processes(&before, &after, 5.0, 50 * MB)), [
+            ("Appeared", "worker".to_string(), 10),
+            ("Disappeared", "worker".to_string(), 10),
+        ]);
+
+        // Without a start time on one side (older exports) the PID and name still match
+        let unknown = [process("worker", 10, 1.0, 10)];
+        assert!(diff_processes(&before[..1], &unknown, 5.0, 50 * MB).is_empty());
+        assert!(diff_processes(&unknown, &after[..1], 5.0, 50 * MB).is_empty());
+    }
+
+    #[test]
+    fn disappeared_processes_keep_the_baseline_order() {
+        let before: Vec<ProcessInfo> = (0..50).rev().map(|pid| process("p", pid, 0.0, 1)).collect();
+        let after = [process("new", 100, 0.0, 1)];
+        let diffs = diff_processes(&before, &after, 5.0, 50 * MB);
+
+        assert!(diffs[0].kind() == ChangeKind::Appeared);
+        let removed: Vec<u32> = diffs[1..].iter().map(|d| d.process().pid).collect();
+        assert_eq!(removed, (0..50).rev().collect::<Vec<_>>());
+    }
+}
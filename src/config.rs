@@ -0,0 +1,649 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+
+use crate::alerts::{AlertConfig, ALERTS_FILE};
+use crate::filter::{ProcessFilter, SavedFilter, FILTERS_FILE};
+use crate::grouping::GroupBy;
+use crate::columns::{self, SortKey, TableColumn, MIN_COLUMN_WIDTH};
+use crate::notifications::ChannelKind;
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::sinks::{SinkConfig, SINKS_FILE};
+use crate::snapshot_log::{SnapshotLogConfig, SNAPSHOT_LOG_FILE};
+use crate::shortcuts::KeyBindings;
+use crate::theme::ThemeSettings;
+use crate::thresholds::ColorThresholds;
+use crate::{metrics, storage, ResourceMonitor, MAX_HISTORY_POINTS};
+
+// Read from the config directory when neither --config nor SYSWATCH_CONFIG names a file
+const CONFIG_FILE: &str = "syswatch.toml";
+// SYSWATCH_PANELS__CHARTS=false overrides panels.charts
+const ENV_PREFIX: &str = "SYSWATCH_";
+const CONFIG_PATH_ENV: &str = "SYSWATCH_CONFIG";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PanelsConfig {
+    pub system_info: bool,
+    pub disk_info: bool,
+    pub users: bool,
+    pub charts: bool,
+}
+
+impl Default for PanelsConfig {
+    fn default() -> Self {
+        Self {
+            system_info: true,
+            disk_info: true,
+            users: false,
+            charts: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TableConfig {
+    // Shown columns in display order; the name column is always shown
+    pub columns: Vec<TableColumn>,
+    // Pixels, per column
+    pub widths: BTreeMap<TableColumn, f32>,
+    pub sort: TableColumn,
+    pub sort_descending: bool,
+    // Further sort keys, applied in order when rows tie
+    pub then_by: Vec<SortKey>,
+    pub group_by: GroupBy,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            columns: TableColumn::DEFAULT.to_vec(),
+            widths: columns::default_widths(),
+            sort: TableColumn::Name,
+            sort_descending: false,
+            then_by: Vec::new(),
+            group_by: GroupBy::None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub addr: String,
+    pub top_n: usize,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            addr: metrics::DEFAULT_METRICS_ADDR.to_string(),
+            top_n: 10,
+        }
+    }
+}
+
+// Everything a deployment can set centrally; also the shape of syswatch.toml
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    // Seconds between updates
+    pub interval: f32,
+    pub retention_hours: f64,
+    pub energy_saving: bool,
+    pub row_height: f32,
+    pub panels: PanelsConfig,
+    pub table: TableConfig,
+    pub colors: ColorThresholds,
+    pub theme: ThemeSettings,
+    pub shortcuts: KeyBindings,
+    pub metrics: MetricsConfig,
+    pub snapshot_log: SnapshotLogConfig,
+    pub sinks: Vec<SinkConfig>,
+    pub alerts: AlertConfig,
+    // Named search queries offered in the Filters menu
+    pub filters: Vec<SavedFilter>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            interval: 1.0,
+            retention_hours: 24.0,
+            energy_saving: false,
+            row_height: 25.0,
+            panels: PanelsConfig::default(),
+            table: TableConfig::default(),
+            colors: ColorThresholds::default(),
+            theme: ThemeSettings::default(),
+            shortcuts: KeyBindings::default(),
+            metrics: MetricsConfig::default(),
+            snapshot_log: SnapshotLogConfig::default(),
+            sinks: Vec::new(),
+            alerts: AlertConfig::default(),
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if !(0.1..=60.0).contains(&self.interval) {
+            errors.push(format!("interval: {} is outside 0.1..60 seconds", self.interval));
+        }
+        if self.retention_hours <= 0.0 || self.retention_hours > 24.0 * 31.0 {
+            errors.push(format!("retention_hours: {} is outside 0..744 hours", self.retention_hours));
+        }
+        if !(20.0..=40.0).contains(&self.row_height) {
+            errors.push(format!("row_height: {} is outside 20..40", self.row_height));
+        }
+
+        for (index, column) in self.table.columns.iter().enumerate() {
+            if self.table.columns[..index].contains(column) {
+                errors.push("table.columns: a column is listed twice".to_string());
+            }
+        }
+        for (index, key) in self.table.then_by.iter().enumerate() {
+            if key.column == self.table.sort || self.table.then_by[..index].iter().any(|k| k.column == key.column) {
+                errors.push(format!("table.then_by[{}]: `{}` is already a sort key", index, key.column.config_key()));
+            }
+        }
+        for (column, width) in &self.table.widths {
+            if *width < MIN_COLUMN_WIDTH {
+                errors.push(format!("table.widths.{}: {} is narrower than {} pixels",
+                    column.config_key(), width, MIN_COLUMN_WIDTH));
+            }
+        }
+
+        errors.extend(self.colors.validate());
+        errors.extend(self.shortcuts.validate());
+
+        if self.metrics.enabled && self.metrics.addr.to_socket_addrs().is_err() {
+            errors.push(format!("metrics.addr: \"{}\" is not a host:port address", self.metrics.addr));
+        }
+        if self.metrics.top_n == 0 {
+            errors.push("metrics.top_n: must be at least 1".to_string());
+        }
+
+        if self.snapshot_log.path.trim().is_empty() {
+            errors.push("snapshot_log.path: must not be empty".to_string());
+        }
+        if self.snapshot_log.keep_files == 0 {
+            errors.push("snapshot_log.keep_files: must be at least 1".to_string());
+        }
+
+        for (index, sink) in self.sinks.iter().enumerate() {
+            if sink.batch_size == 0 {
+                errors.push(format!("sinks[{}].batch_size: must be at least 1", index));
+            }
+        }
+
+        for (index, rule) in self.alerts.rules.iter().enumerate() {
+            if self.alerts.rules[..index].iter().any(|r| r.id == rule.id) {
+                errors.push(format!("alerts.rules[{}].id: {} is used by another rule", index, rule.id));
+            }
+            if !rule.threshold.is_finite() {
+                errors.push(format!("alerts.rules[{}].threshold: must be a number", index));
+            }
+            if rule.duration_secs < 0.0 || rule.hysteresis < 0.0 {
+                errors.push(format!("alerts.rules[{}]: duration_secs and hysteresis must not be negative", index));
+            }
+        }
+        for (index, channel) in self.alerts.channels.iter().enumerate() {
+            match &channel.kind {
+                ChannelKind::Webhook { url } if !url.starts_with("http://") && !url.starts_with("https://") => {
+                    errors.push(format!("alerts.channels[{}].url: \"{}\" is not an http(s) URL", index, url));
+                }
+                ChannelKind::Command { command } if command.trim().is_empty() => {
+                    errors.push(format!("alerts.channels[{}].command: must not be empty", index));
+                }
+                _ => {}
+            }
+        }
+
+        for (index, filter) in self.filters.iter().enumerate() {
+            if filter.name.trim().is_empty() {
+                errors.push(format!("filters[{}].name: must not be empty", index));
+            } else if self.filters[..index].iter().any(|f| f.name == filter.name) {
+                errors.push(format!("filters[{}].name: \"{}\" is used by another filter", index, filter.name));
+            }
+            if let Err(e) = ProcessFilter::parse(&filter.query) {
+                errors.push(format!("filters[{}].query: {}", index, e));
+            }
+        }
+
+        errors
+    }
+}
+
+pub struct LoadedConfig {
+    pub config: Config,
+    // Where values came from, lowest precedence first
+    pub sources: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// Values as typed on the command line or in the environment: TOML syntax, anything else is a string
+pub fn parse_value(raw: &str) -> toml::Value {
+    match format!("value = {}", raw).parse::<toml::Table>() {
+        Ok(mut table) => table.remove("value").unwrap_or_else(|| toml::Value::String(raw.to_string())),
+        Err(_) => toml::Value::String(raw.to_string()),
+    }
+}
+
+fn lookup<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(value, |value, part| value.as_table()?.get(part))
+}
+
+fn lookup_mut<'a>(value: &'a mut toml::Value, key: &str) -> Option<&'a mut toml::Value> {
+    key.split('.').try_fold(value, |value, part| value.as_table_mut()?.get_mut(part))
+}
+
+// Layers the configuration, checking each key on its own so one bad value doesn't discard the rest
+struct Merger {
+    defaults: toml::Value,
+    merged: toml::Value,
+    errors: Vec<String>,
+}
+
+impl Merger {
+    fn set(&mut self, key: &str, value: toml::Value, source: &str) {
+        let Some(default) = lookup(&self.defaults, key) else {
+            self.errors.push(format!("{}: unknown key `{}`", source, key));
+            return;
+        };
+
+        // Tables are merged key by key, everything else (including arrays) is replaced
+        if let (true, toml::Value::Table(table)) = (default.is_table(), &value) {
+            for (name, value) in table.clone() {
+                self.set(&format!("{}.{}", key, name), value, source);
+            }
+            return;
+        }
+
+        let slot = lookup_mut(&mut self.merged, key).expect("key exists in the defaults");
+        let previous = std::mem::replace(slot, value);
+        if let Err(e) = self.merged.clone().try_into::<Config>() {
+            self.errors.push(format!("{}: `{}`: {}", source, key, e.message()));
+            *lookup_mut(&mut self.merged, key).expect("key exists in the defaults") = previous;
+        }
+    }
+
+    fn set_all(&mut self, table: toml::Table, source: &str) {
+        for (key, value) in table {
+            self.set(&key, value, source);
+        }
+    }
+}
+
+// State saved by the UI (settings, rules, sinks, log, filters), in the config file's layout
+fn saved_state() -> toml::Table {
+    let mut table = toml::Table::new();
+    let mut insert = |key: &str, value: Result<toml::Value, toml::ser::Error>| {
+        if let Ok(value) = value {
+            table.insert(key.to_string(), value);
+        }
+    };
+
+    if let Some(settings) = storage::load_json::<Settings>(SETTINGS_FILE) {
+        insert("interval", toml::Value::try_from(settings.update_interval));
+        insert("energy_saving", toml::Value::try_from(settings.energy_saving_mode));
+        insert("row_height", toml::Value::try_from(settings.row_height));
+        insert("panels", toml::Value::try_from(PanelsConfig {
+            system_info: settings.show_system_info,
+            disk_info: settings.show_disk_info,
+            users: settings.show_users,
+            charts: settings.show_charts,
+        }));
+        let mut sorting = toml::Table::new();
+        if let Ok(sort) = toml::Value::try_from(settings.sort_column) {
+            sorting.insert("sort".to_string(), sort);
+        }
+        sorting.insert("sort_descending".to_string(), toml::Value::Boolean(settings.sort_descending));
+        if let Ok(then_by) = toml::Value::try_from(&settings.then_by) {
+            sorting.insert("then_by".to_string(), then_by);
+        }
+        if let Ok(group_by) = toml::Value::try_from(settings.group_by) {
+            sorting.insert("group_by".to_string(), group_by);
+        }
+        if let Ok(columns) = toml::Value::try_from(&settings.columns) {
+            sorting.insert("columns".to_string(), columns);
+        }
+        if let Ok(widths) = toml::Value::try_from(&settings.column_widths) {
+            sorting.insert("widths".to_string(), widths);
+        }
+        insert("table", Ok(toml::Value::Table(sorting)));
+        insert("colors", toml::Value::try_from(&settings.color_thresholds));
+        insert("theme", toml::Value::try_from(&settings.theme));
+    }
+    if let Some(alerts) = storage::load_json::<AlertConfig>(ALERTS_FILE) {
+        insert("alerts", toml::Value::try_from(alerts));
+    }
+    if let Some(sinks) = storage::load_json::<Vec<SinkConfig>>(SINKS_FILE) {
+        insert("sinks", toml::Value::try_from(sinks));
+    }
+    if let Some(snapshot_log) = storage::load_json::<SnapshotLogConfig>(SNAPSHOT_LOG_FILE) {
+        insert("snapshot_log", toml::Value::try_from(snapshot_log));
+    }
+    if let Some(filters) = storage::load_json::<Vec<SavedFilter>>(FILTERS_FILE) {
+        insert("filters", toml::Value::try_from(filters));
+    }
+    table
+}
+
+// Precedence, lowest first: defaults, state saved by the UI, config file, environment, command line
+pub fn load(config_path: Option<&Path>, cli_overrides: &[(String, toml::Value)]) -> LoadedConfig {
+    let explicit_path = config_path
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from));
+    let path = explicit_path.clone().or_else(|| storage::config_dir().map(|dir| dir.join(CONFIG_FILE)));
+    let file = path.and_then(|path| match std::fs::read_to_string(&path) {
+        Ok(contents) => Some(contents
+            .parse::<toml::Table>()
+            .map(|table| (path.display().to_string(), table))
+            .map_err(|e| format!("{}: {}", path.display(), e))),
+        // Only a missing default file is fine
+        Err(e) if explicit_path.is_some() || e.kind() != std::io::ErrorKind::NotFound => {
+            Some(Err(format!("{}: {}", path.display(), e)))
+        }
+        Err(_) => None,
+    });
+
+    merge(saved_state(), file, std::env::vars(), cli_overrides)
+}
+
+// Layers the sources over the defaults; `file` is the config file's name and contents, if there is one
+fn merge(
+    saved: toml::Table,
+    file: Option<Result<(String, toml::Table), String>>,
+    env: impl IntoIterator<Item = (String, String)>,
+    cli_overrides: &[(String, toml::Value)],
+) -> LoadedConfig {
+    let defaults = toml::Value::try_from(Config::default()).expect("default config serializes");
+    let mut merger = Merger {
+        merged: defaults.clone(),
+        defaults,
+        errors: Vec::new(),
+    };
+    let mut sources = vec!["defaults".to_string()];
+
+    if !saved.is_empty() {
+        merger.set_all(saved, "saved UI state");
+        sources.push("saved UI state".to_string());
+    }
+
+    match file {
+        Some(Ok((source, table))) => {
+            merger.set_all(table, &source);
+            sources.push(source);
+        }
+        Some(Err(e)) => merger.errors.push(e),
+        None => {}
+    }
+
+    let mut from_env = false;
+    for (name, raw) in env {
+        let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if name == CONFIG_PATH_ENV {
+            continue;
+        }
+        let key = key.to_lowercase().replace("__", ".");
+        merger.set(&key, parse_value(&raw), &format!("environment variable {}", name));
+        from_env = true;
+    }
+    if from_env {
+        sources.push("environment".to_string());
+    }
+
+    for (key, value) in cli_overrides {
+        merger.set(key, value.clone(), "command line");
+    }
+    if !cli_overrides.is_empty() {
+        sources.push("command line".to_string());
+    }
+
+    let mut errors = merger.errors;
+    let config: Config = merger.merged.try_into().unwrap_or_else(|e: toml::de::Error| {
+        errors.push(e.message().to_string());
+        Config::default()
+    });
+    errors.extend(config.validate());
+
+    LoadedConfig { config, sources, errors }
+}
+
+// --print-config: the effective configuration on stdout, problems on stderr
+pub fn print(loaded: &LoadedConfig) {
+    println!("# Effective SysWatch configuration");
+    println!("# Sources, lowest precedence first: {}", loaded.sources.join(", "));
+    match toml::to_string_pretty(&loaded.config) {
+        Ok(text) => print!("{}", text),
+        Err(e) => eprintln!("Error printing configuration: {}", e),
+    }
+    for error in &loaded.errors {
+        eprintln!("error: {}", error);
+    }
+}
+
+impl ResourceMonitor {
+    pub(crate) fn apply_config(&mut self, config: &Config) {
+        self.update_interval = config.interval.clamp(0.1, 60.0);
+        self.history_retention_secs = config.retention_hours.max(0.0) * 3600.0;
+        // One history sample per update, up to the budget; longer retentions keep older samples thinned
+        self.max_history_points = ((self.history_retention_secs / self.update_interval as f64) as usize)
+            .clamp(2, MAX_HISTORY_POINTS);
+        self.energy_saving_mode = config.energy_saving;
+        self.row_height = config.row_height.clamp(20.0, 40.0);
+
+        self.show_system_info = config.panels.system_info;
+        self.show_disk_info = config.panels.disk_info;
+        self.show_users = config.panels.users;
+        self.show_charts = config.panels.charts;
+
+        self.set_columns(&config.table.columns, &config.table.widths);
+        self.sort_column = config.table.sort;
+        self.sort_descending = config.table.sort_descending;
+        self.then_by = config.table.then_by.clone();
+        self.group_by = config.table.group_by;
+        self.color_thresholds = config.colors.clone();
+        self.theme = config.theme.clone();
+        self.key_bindings = config.shortcuts.clone();
+
+        self.metrics_addr = config.metrics.addr.clone();
+        self.metrics_top_n = config.metrics.top_n.max(1);
+        if config.metrics.enabled {
+            self.start_metrics_server();
+        }
+
+        self.snapshot_log_config = config.snapshot_log.clone();
+        self.start_snapshot_log();
+        self.sink_configs = config.sinks.clone();
+        self.saved_filters = config.filters.clone();
+        self.start_sinks();
+        self.alert_config = config.alerts.clone();
+        self.start_notifiers();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(text: &str) -> toml::Table {
+        text.parse().unwrap()
+    }
+
+    fn file(text: &str) -> Option<Result<(String, toml::Table), String>> {
+        Some(Ok(("syswatch.toml".to_string(), table(text))))
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn cli(key: &str, raw: &str) -> (String, toml::Value) {
+        (key.to_string(), parse_value(raw))
+    }
+
+    #[test]
+    fn defaults_alone() {
+        let loaded = merge(toml::Table::new(), None, Vec::new(), &[]);
+        assert_eq!(loaded.sources, ["defaults"]);
+        assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+        assert_eq!(loaded.config.interval, 1.0);
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let saved = table("interval = 2.0\nenergy_saving = true\nrow_height = 22.0");
+        let loaded = merge(
+            saved,
+            file("interval = 3.0\nrow_height = 30.0\nretention_hours = 2.0"),
+            env(&[("SYSWATCH_INTERVAL", "4"), ("SYSWATCH_RETENTION_HOURS", "3"), ("HOME", "/root")]),
+            &[cli("interval", "5")],
+        );
+        assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+        assert_eq!(loaded.sources, ["defaults", "saved UI state", "syswatch.toml", "environment", "command line"]);
+        let config = loaded.config;
+        assert_eq!(config.interval, 5.0);
+        assert_eq!(config.retention_hours, 3.0);
+        assert_eq!(config.row_height, 30.0);
+        assert!(config.energy_saving);
+    }
+
+    #[test]
+    fn tables_merge_and_arrays_replace() {
+        let saved = table("[panels]\ncharts = false\n[table]\ncolumns = [\"name\", \"cpu\", \"pid\"]");
+        let loaded = merge(
+            saved,
+            file("[panels]\nusers = true\n[table]\ncolumns = [\"name\", \"memory\"]"),
+            env(&[("SYSWATCH_PANELS__DISK_INFO", "false")]),
+            &[cli("table.sort", "\"cpu\"")],
+        );
+        assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+        let config = loaded.config;
+        assert!(!config.panels.charts);
+        assert!(config.panels.users);
+        assert!(!config.panels.disk_info);
+        assert!(config.panels.system_info);
+        assert!(config.table.columns == [TableColumn::Name, TableColumn::Memory]);
+        assert!(config.table.sort == TableColumn::Cpu);
+    }
+
+    #[test]
+    fn the_config_path_variable_is_not_a_key() {
+        let loaded = merge(toml::Table::new(), None, env(&[("SYSWATCH_CONFIG", "/etc/syswatch.toml")]), &[]);
+        assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+        assert_eq!(loaded.sources, ["defaults"]);
+    }
+
+    #[test]
+    fn bad_values_are_reported_and_skipped() {
+        let loaded = merge(
+            table("interval = 2.0"),
+            file("interval = \"fast\"\nrow_height = 30.0\ncolour = \"red\"\n[panels]\nchart = true"),
+            env(&[("SYSWATCH_ENERGY_SAVING", "maybe")]),
+            &[cli("metrics.top_n", "-1")],
+        );
+        let errors = loaded.errors;
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(errors[0].starts_with("syswatch.toml: unknown key `colour`"), "{}", errors[0]);
+        assert!(errors.iter().any(|e| e.starts_with("syswatch.toml: `interval`: ")), "{:?}", errors);
+        assert!(errors.contains(&"syswatch.toml: unknown key `panels.chart`".to_string()), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.starts_with("environment variable SYSWATCH_ENERGY_SAVING: `energy_saving`: ")));
+        assert!(errors[4].starts_with("command line: `metrics.top_n`: "), "{}", errors[4]);
+
+        // The rest of each source still applies, the bad values keep what was there before
+        assert_eq!(loaded.config.interval, 2.0);
+        assert_eq!(loaded.config.row_height, 30.0);
+        assert!(!loaded.config.energy_saving);
+        assert_eq!(loaded.config.metrics.top_n, 10);
+    }
+
+    #[test]
+    fn unreadable_files_are_reported() {
+        let loaded = merge(toml::Table::new(), Some(Err("syswatch.toml: expected `=`".to_string())), Vec::new(), &[]);
+        assert_eq!(loaded.errors, ["syswatch.toml: expected `=`"]);
+        assert_eq!(loaded.sources, ["defaults"]);
+    }
+
+    #[test]
+    fn parsed_values_fall_back_to_strings() {
+        assert_eq!(parse_value("5"), toml::Value::Integer(5));
+        assert_eq!(parse_value("false"), toml::Value::Boolean(false));
+        assert_eq!(parse_value("[\"name\", \"pid\"]"), toml::Value::Array(vec!["name".into(), "pid".into()]));
+        assert_eq!(parse_value("0.0.0.0:9184"), toml::Value::String("0.0.0.0:9184".to_string()));
+    }
+
+    #[test]
+    fn validation() {
+        let loaded = merge(
+            toml::Table::new(),
+            file(r#"
+                interval = 0.0
+                retention_hours = 0.0
+                row_height = 50.0
+                [table]
+                columns = ["name", "cpu", "cpu"]
+                sort = "cpu"
+                then_by = [{ column = "cpu", descending = true }, { column = "pid", descending = false }]
+                widths = { pid = 10.0 }
+                [metrics]
+                enabled = true
+                addr = "nowhere"
+                top_n = 0
+                [snapshot_log]
+                path = " "
+                keep_files = 0
+                [[sinks]]
+                enabled = true
+                batch_size = 0
+                kind = { type = "influx_file", path = "out.lp" }
+                [[filters]]
+                name = "heavy"
+                query = "cpu>"
+                [[filters]]
+                name = "heavy"
+                query = "cpu>50"
+            "#),
+            Vec::new(),
+            &[],
+        );
+        assert_eq!(loaded.errors, [
+            "interval: 0 is outside 0.1..60 seconds",
+            "retention_hours: 0 is outside 0..744 hours",
+            "row_height: 50 is outside 20..40",
+            "table.columns: a column is listed twice",
+            "table.then_by[0]: `cpu` is already a sort key",
+            "table.widths.pid: 10 is narrower than 40 pixels",
+            "metrics.addr: \"nowhere\" is not a host:port address",
+            "metrics.top_n: must be at least 1",
+            "snapshot_log.path: must not be empty",
+            "snapshot_log.keep_files: must be at least 1",
+            "sinks[0].batch_size: must be at least 1",
+            "filters[0].query: column 5: `cpu` needs a value",
+            "filters[1].name: \"heavy\" is used by another filter",
+        ]);
+    }
+
+    #[test]
+    fn alert_validation() {
+        let mut config = Config::default();
+        config.alerts.rules[1].id = config.alerts.rules[0].id;
+        config.alerts.rules[2].threshold = f64::NAN;
+        config.alerts.rules[3].hysteresis = -1.0;
+        assert_eq!(config.validate(), [
+            "alerts.rules[1].id: 1 is used by another rule",
+            "alerts.rules[2].threshold: must be a number",
+            "alerts.rules[3]: duration_secs and hysteresis must not be negative",
+        ]);
+    }
+}
@@ -0,0 +1,457 @@
+use eframe::egui;
+use chrono::Local;
+use serde::Serialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::columns::TableColumn;
+use crate::theme::color;
+use crate::{format_timestamp, HistorySample, ProcessInfo, ProcessSample, ResourceMonitor};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+// What part of the collected data gets written
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportScope {
+    FilteredView,
+    AllProcesses,
+    Disks,
+    SystemTotals,
+    History,
+}
+
+impl ExportScope {
+    pub const ALL: [ExportScope; 5] = [
+        ExportScope::FilteredView,
+        ExportScope::AllProcesses,
+        ExportScope::Disks,
+        ExportScope::SystemTotals,
+        ExportScope::History,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportScope::FilteredView => "Processes shown in the table (search applied)",
+            ExportScope::AllProcesses => "All processes (search ignored)",
+            ExportScope::Disks => "Disks",
+            ExportScope::SystemTotals => "System totals",
+            ExportScope::History => "Full history",
+        }
+    }
+
+    // Prefix of the suggested file name
+    fn file_stem(&self) -> &'static str {
+        match self {
+            ExportScope::FilteredView => "processes",
+            ExportScope::AllProcesses => "all_processes",
+            ExportScope::Disks => "disks",
+            ExportScope::SystemTotals => "system",
+            ExportScope::History => "history",
+        }
+    }
+
+    fn has_process_columns(&self) -> bool {
+        matches!(self, ExportScope::FilteredView | ExportScope::AllProcesses)
+    }
+}
+
+// Name of a column's field in exported files; the `ProcessInfo` field, so exports open as snapshots
+fn export_key(column: TableColumn) -> &'static str {
+    match column {
+        TableColumn::Name => "name",
+        TableColumn::Pid => "pid",
+        TableColumn::ParentPid => "parent_pid",
+        TableColumn::Cpu => "cpu_usage",
+        TableColumn::Memory => "memory_usage",
+        TableColumn::VirtualMemory => "virtual_memory",
+        TableColumn::Threads => "threads",
+        TableColumn::Status => "status",
+        TableColumn::User => "user",
+        TableColumn::EffectiveUser => "effective_user",
+        TableColumn::Group => "group",
+        TableColumn::EffectiveGroup => "effective_group",
+        TableColumn::Nice => "nice",
+        TableColumn::StartTime => "start_time",
+        TableColumn::RunTime => "run_time",
+        TableColumn::DiskRead => "disk_read_per_sec",
+        TableColumn::DiskWrite => "disk_write_per_sec",
+        TableColumn::CommandLine => "command_line",
+        TableColumn::Exe => "exe",
+    }
+}
+
+fn json_value(column: TableColumn, process: &ProcessInfo) -> serde_json::Value {
+    match column {
+        TableColumn::Name => process.name.clone().into(),
+        TableColumn::Pid => process.pid.into(),
+        TableColumn::ParentPid => process.parent_pid.into(),
+        TableColumn::Cpu => process.cpu_usage.into(),
+        TableColumn::Memory => process.memory_usage.into(),
+        TableColumn::VirtualMemory => process.virtual_memory.into(),
+        TableColumn::Threads => process.threads.into(),
+        TableColumn::Status => process.status.clone().into(),
+        TableColumn::User => process.user.clone().into(),
+        TableColumn::EffectiveUser => process.effective_user.clone().into(),
+        TableColumn::Group => process.group.clone().into(),
+        TableColumn::EffectiveGroup => process.effective_group.clone().into(),
+        TableColumn::Nice => process.nice.into(),
+        TableColumn::StartTime => process.start_time.into(),
+        TableColumn::RunTime => process.run_time.into(),
+        TableColumn::DiskRead => process.disk_read_per_sec.into(),
+        TableColumn::DiskWrite => process.disk_write_per_sec.into(),
+        TableColumn::CommandLine => process.command_line.clone().into(),
+        TableColumn::Exe => process.exe.clone().into(),
+    }
+}
+
+fn csv_value(column: TableColumn, process: &ProcessInfo) -> String {
+    fn optional<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+    match column {
+        TableColumn::Name => process.name.clone(),
+        TableColumn::Pid => process.pid.to_string(),
+        TableColumn::ParentPid => optional(process.parent_pid),
+        TableColumn::Cpu => process.cpu_usage.to_string(),
+        TableColumn::Memory => process.memory_usage.to_string(),
+        TableColumn::VirtualMemory => process.virtual_memory.to_string(),
+        TableColumn::Threads => optional(process.threads),
+        TableColumn::Status => process.status.clone(),
+        TableColumn::User => optional(process.user.as_deref()),
+        TableColumn::EffectiveUser => optional(process.effective_user.as_deref()),
+        TableColumn::Group => optional(process.group.as_deref()),
+        TableColumn::EffectiveGroup => optional(process.effective_group.as_deref()),
+        TableColumn::Nice => optional(process.nice),
+        TableColumn::StartTime => process.start_time.to_string(),
+        TableColumn::RunTime => process.run_time.to_string(),
+        TableColumn::DiskRead => process.disk_read_per_sec.to_string(),
+        TableColumn::DiskWrite => process.disk_write_per_sec.to_string(),
+        TableColumn::CommandLine => optional(process.command_line.as_deref()),
+        TableColumn::Exe => optional(process.exe.as_deref()),
+    }
+}
+
+// Flat form of a history sample, since CSV cannot hold nested lists
+#[derive(Serialize)]
+struct HistoryRow {
+    timestamp: f64,
+    time: String,
+    cpu_usage: f64,
+    memory_mb: f64,
+    top_cpu: String,
+    top_memory: String,
+}
+
+impl HistoryRow {
+    fn new(sample: &HistorySample) -> Self {
+        Self {
+            timestamp: sample.timestamp,
+            time: format_timestamp(sample.timestamp, "%Y-%m-%d %H:%M:%S"),
+            cpu_usage: sample.cpu_usage,
+            memory_mb: sample.memory_mb,
+            top_cpu: summarize_processes(&sample.top_cpu),
+            top_memory: summarize_processes(&sample.top_memory),
+        }
+    }
+}
+
+fn summarize_processes(processes: &[ProcessSample]) -> String {
+    processes
+        .iter()
+        .map(|p| format!("{} ({})", p.name, p.pid))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub fn processes_to_json(processes: &[&ProcessInfo], columns: &[TableColumn]) -> serde_json::Result<String> {
+    let rows: Vec<serde_json::Value> = processes
+        .iter()
+        .map(|process| {
+            let row: serde_json::Map<String, serde_json::Value> = columns
+                .iter()
+                .map(|&column| (export_key(column).to_string(), json_value(column, process)))
+                .collect();
+            serde_json::Value::Object(row)
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows)
+}
+
+pub fn processes_to_csv(processes: &[&ProcessInfo], columns: &[TableColumn]) -> Result<String, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(columns.iter().map(|&c| export_key(c)))?;
+    for process in processes {
+        writer.write_record(columns.iter().map(|&c| csv_value(c, process)))?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+// Writes the processes whole, every `ProcessInfo` field included, to a new file in the directory
+fn write_snapshot(directory: &Path, processes: &[ProcessInfo]) -> Result<PathBuf, Box<dyn Error>> {
+    let path = directory.join(format!("snapshot_{}.json", Local::now().format("%Y%m%d_%H%M%S")));
+    std::fs::create_dir_all(directory)?;
+    std::fs::write(&path, serde_json::to_string_pretty(processes)?)?;
+    Ok(path)
+}
+
+fn rows_to_csv<T: Serialize>(rows: &[T]) -> Result<String, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn rows_to_json<T: Serialize>(rows: &[T]) -> Result<String, Box<dyn Error>> {
+    Ok(serde_json::to_string_pretty(rows)?)
+}
+
+impl ResourceMonitor {
+    // The table's columns from left to right, then the hidden ones
+    fn export_column_order(&self) -> Vec<TableColumn> {
+        let mut columns = self.visible_columns.clone();
+        columns.extend(TableColumn::ALL.into_iter().filter(|c| !self.visible_columns.contains(c)));
+        columns
+    }
+
+    // Serializes the chosen scope in the chosen format
+    pub(crate) fn export_contents(&self, format: ExportFormat, scope: ExportScope) -> Result<String, Box<dyn Error>> {
+        // Keep the table's column order regardless of the order they were ticked in
+        let columns: Vec<TableColumn> = self.export_column_order()
+            .into_iter()
+            .filter(|c| self.export_columns.contains(c))
+            .collect();
+
+        match scope {
+            ExportScope::FilteredView | ExportScope::AllProcesses => {
+                let processes: Vec<&ProcessInfo> = if scope == ExportScope::FilteredView {
+                    self.process_indices.iter().map(|&i| &self.processes[i]).collect()
+                } else {
+                    // Same order as the table, rows hidden by the search included
+                    let mut processes: Vec<&ProcessInfo> = self.processes.iter().collect();
+                    processes.sort_by(|a, b| self.compare_rows(a, b));
+                    processes
+                };
+                match format {
+                    ExportFormat::Json => Ok(processes_to_json(&processes, &columns)?),
+                    ExportFormat::Csv => processes_to_csv(&processes, &columns),
+                }
+            }
+            ExportScope::Disks => match format {
+                ExportFormat::Json => rows_to_json(&self.disks),
+                ExportFormat::Csv => rows_to_csv(&self.disks),
+            },
+            ExportScope::SystemTotals => {
+                let totals = [self.system_totals()];
+                match format {
+                    ExportFormat::Json => rows_to_json(&totals),
+                    ExportFormat::Csv => rows_to_csv(&totals),
+                }
+            }
+            ExportScope::History => match format {
+                ExportFormat::Json => {
+                    let samples: Vec<&HistorySample> = self.history.iter().collect();
+                    rows_to_json(&samples)
+                }
+                ExportFormat::Csv => {
+                    let rows: Vec<HistoryRow> = self.history.iter().map(HistoryRow::new).collect();
+                    rows_to_csv(&rows)
+                }
+            },
+        }
+    }
+
+    pub(crate) fn export_to(&self, path: &Path, format: ExportFormat, scope: ExportScope) -> Result<(), Box<dyn Error>> {
+        let contents = self.export_contents(format, scope)?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    // The given live processes, in the form "Open snapshot..." reads back
+    pub(crate) fn export_full_snapshot(&self, directory: &Path, processes: &[ProcessInfo]) -> Result<PathBuf, Box<dyn Error>> {
+        write_snapshot(directory, processes)
+    }
+
+    fn default_export_file_name(&self) -> String {
+        format!("{}_{}.{}",
+            self.export_scope.file_stem(),
+            Local::now().format("%Y%m%d_%H%M%S"),
+            self.export_format.extension())
+    }
+
+    pub(crate) fn render_export_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_export_window;
+
+        egui::Window::new("Export")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Format: ");
+                    for format in [ExportFormat::Json, ExportFormat::Csv] {
+                        ui.radio_value(&mut self.export_format, format, format.label());
+                    }
+                });
+
+                ui.separator();
+                ui.label("Scope: ");
+                for scope in ExportScope::ALL {
+                    ui.radio_value(&mut self.export_scope, scope, scope.label());
+                }
+
+                if self.export_scope.has_process_columns() {
+                    let count = match self.export_scope {
+                        ExportScope::FilteredView => self.process_indices.len(),
+                        _ => self.processes.len(),
+                    };
+                    let mut note = format!("{} of {} processes", count, self.processes.len());
+                    if self.export_scope == ExportScope::FilteredView && !self.process_filter.trim().is_empty() {
+                        note.push_str(&format!(", matching \"{}\"", self.process_filter.trim()));
+                    }
+                    ui.label(egui::RichText::new(note).color(ui.visuals().weak_text_color()));
+
+                    ui.separator();
+                    ui.label("Columns: ");
+                    ui.horizontal_wrapped(|ui| {
+                        for column in self.export_column_order() {
+                            let mut enabled = self.export_columns.contains(&column);
+                            if ui.checkbox(&mut enabled, column.label()).changed() {
+                                if enabled {
+                                    self.export_columns.push(column);
+                                } else {
+                                    self.export_columns.retain(|c| *c != column);
+                                }
+                            }
+                        }
+                    });
+                }
+
+                ui.separator();
+
+                let can_export = !self.export_scope.has_process_columns() || !self.export_columns.is_empty();
+                if ui.add_enabled(can_export, egui::Button::new("Save...")).clicked() {
+                    let format = self.export_format;
+                    let dialog = rfd::FileDialog::new()
+                        .set_file_name(self.default_export_file_name())
+                        .add_filter(format.label(), &[format.extension()]);
+
+                    if let Some(path) = dialog.save_file() {
+                        self.export_status = Some(match self.export_to(&path, format, self.export_scope) {
+                            Ok(()) => (format!("Saved to {}", path.display()), true),
+                            Err(e) => {
+                                eprintln!("Error writing file: {}", e);
+                                (format!("Error writing file: {}", e), false)
+                            }
+                        });
+                    }
+                }
+
+                if let Some((message, success)) = &self.export_status {
+                    let color = if *success { color(self.palette.good) } else { ui.visuals().error_fg_color };
+                    ui.label(egui::RichText::new(message).color(color));
+                }
+            });
+
+        self.show_export_window = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::Snapshot;
+
+    fn process() -> ProcessInfo {
+        ProcessInfo {
+            name: "nginx".to_string(),
+            pid: 812,
+            cpu_usage: 1.5,
+            memory_usage: 4_194_304,
+            status: "Sleeping".to_string(),
+            user: Some("root".to_string()),
+            effective_user: Some("www-data".to_string()),
+            group: Some("root".to_string()),
+            effective_group: Some("www-data".to_string()),
+            command_line: Some("nginx -g daemon off;".to_string()),
+            start_time: 1_700_000_000,
+            parent_pid: Some(1),
+            threads: Some(4),
+            virtual_memory: 16_777_216,
+            nice: Some(-5),
+            run_time: 3600,
+            disk_read_per_sec: 512.0,
+            disk_write_per_sec: 0.0,
+            exe: Some("/usr/sbin/nginx".to_string()),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("syswatch_{}_{}", name, std::process::id()))
+    }
+
+    fn assert_same(loaded: &ProcessInfo, expected: &ProcessInfo) {
+        assert_eq!(serde_json::to_value(loaded).unwrap(), serde_json::to_value(expected).unwrap());
+    }
+
+    #[test]
+    fn full_snapshots_keep_every_field() {
+        let directory = temp_dir("snapshot");
+        let expected = process();
+        let path = write_snapshot(&directory, std::slice::from_ref(&expected)).unwrap();
+        let snapshot = Snapshot::load(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(snapshot.processes.len(), 1);
+        assert!(snapshot.processes[0].key() == expected.key());
+        assert_same(&snapshot.processes[0], &expected);
+    }
+
+    #[test]
+    fn exports_with_every_column_open_as_snapshots() {
+        let directory = temp_dir("export");
+        std::fs::create_dir_all(&directory).unwrap();
+        let expected = process();
+        let unknown = ProcessInfo { name: "kthreadd".to_string(), pid: 2, ..Default::default() };
+
+        let json = directory.join("processes.json");
+        std::fs::write(&json, processes_to_json(&[&expected, &unknown], &TableColumn::ALL).unwrap()).unwrap();
+        let csv = directory.join("processes.csv");
+        std::fs::write(&csv, processes_to_csv(&[&expected, &unknown], &TableColumn::ALL).unwrap()).unwrap();
+
+        let from_json = Snapshot::load(&json).unwrap();
+        let from_csv = Snapshot::load(&csv).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        for snapshot in [from_json, from_csv] {
+            assert_same(&snapshot.processes[0], &expected);
+            assert_same(&snapshot.processes[1], &unknown);
+        }
+    }
+
+    #[test]
+    fn csv_columns_follow_the_given_order() {
+        let csv = processes_to_csv(&[&process()], &[TableColumn::Pid, TableColumn::Name, TableColumn::Nice]).unwrap();
+        assert_eq!(csv, "pid,name,nice\n812,nginx,-5\n");
+    }
+}
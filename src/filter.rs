@@ -0,0 +1,571 @@
+use eframe::egui;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{storage, ProcessInfo, ResourceMonitor};
+
+pub(crate) const FILTERS_FILE: &str = "filters.json";
+
+// Shown when hovering the search field
+pub const QUERY_HELP: &str = "Words match the process name; all terms must match.\n\
+    name: user: euser: group: egroup: status: cmd: exe:  contain the text (case-insensitive)\n\
+    name= user= ...  equal the text\n\
+    name~ cmd~ ...  match a regular expression, e.g. cmd~\"--port 54\\d\\d\"\n\
+    /regex/  matches the name\n\
+    cpu>5  mem>1G  vmem<=500M  read>1M  write>0  (sizes without a unit are MB)\n\
+    pid=1234  ppid:1  threads>=10  nice<0\n\
+    -term or !term negates; quote values with spaces";
+
+// A named query kept in the Filters menu
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedFilter {
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(Clone, Copy)]
+enum TextField {
+    Name,
+    User,
+    EffectiveUser,
+    Group,
+    EffectiveGroup,
+    Status,
+    Command,
+    Exe,
+}
+
+#[derive(Clone, Copy)]
+enum NumberField {
+    Cpu,
+    Memory,
+    VirtualMemory,
+    Pid,
+    ParentPid,
+    Threads,
+    Nice,
+    DiskRead,
+    DiskWrite,
+}
+
+impl NumberField {
+    // Values are given in bytes with an optional unit
+    fn is_size(&self) -> bool {
+        matches!(self, NumberField::Memory | NumberField::VirtualMemory | NumberField::DiskRead | NumberField::DiskWrite)
+    }
+}
+
+enum Field {
+    Text(TextField),
+    Number(NumberField),
+}
+
+fn field(name: &str) -> Option<Field> {
+    Some(match name.to_lowercase().as_str() {
+        "name" => Field::Text(TextField::Name),
+        "user" => Field::Text(TextField::User),
+        "euser" => Field::Text(TextField::EffectiveUser),
+        "group" => Field::Text(TextField::Group),
+        "egroup" => Field::Text(TextField::EffectiveGroup),
+        "status" => Field::Text(TextField::Status),
+        "cmd" | "command" => Field::Text(TextField::Command),
+        "exe" | "path" => Field::Text(TextField::Exe),
+        "cpu" => Field::Number(NumberField::Cpu),
+        "mem" | "memory" | "rss" => Field::Number(NumberField::Memory),
+        "vmem" | "virt" => Field::Number(NumberField::VirtualMemory),
+        "pid" => Field::Number(NumberField::Pid),
+        "ppid" => Field::Number(NumberField::ParentPid),
+        "threads" => Field::Number(NumberField::Threads),
+        "nice" => Field::Number(NumberField::Nice),
+        "read" => Field::Number(NumberField::DiskRead),
+        "write" => Field::Number(NumberField::DiskWrite),
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operator {
+    Contains,
+    Equal,
+    Regex,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+enum Condition {
+    Contains(TextField, String),
+    Equals(TextField, String),
+    Matches(TextField, Regex),
+    Compare(NumberField, Operator, f64),
+}
+
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+// A parsed search query; every term has to match
+#[derive(Default)]
+pub struct ProcessFilter {
+    terms: Vec<Term>,
+}
+
+impl ProcessFilter {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parser = Parser { chars: query.chars().collect(), position: 0 };
+        let mut terms = Vec::new();
+        while let Some(term) = parser.term()? {
+            terms.push(term);
+        }
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        self.terms.iter().all(|term| term.condition.matches(process) != term.negated)
+    }
+}
+
+impl Condition {
+    fn matches(&self, process: &ProcessInfo) -> bool {
+        match self {
+            Condition::Contains(field, text) => text_of(process, *field).is_some_and(|v| v.to_lowercase().contains(text)),
+            Condition::Equals(field, text) => text_of(process, *field).is_some_and(|v| v.to_lowercase() == *text),
+            Condition::Matches(field, regex) => text_of(process, *field).is_some_and(|v| regex.is_match(v)),
+            Condition::Compare(field, operator, wanted) => number_of(process, *field).is_some_and(|value| match operator {
+                Operator::Greater => value > *wanted,
+                Operator::GreaterOrEqual => value >= *wanted,
+                Operator::Less => value < *wanted,
+                Operator::LessOrEqual => value <= *wanted,
+                _ => value == *wanted,
+            }),
+        }
+    }
+}
+
+fn text_of(process: &ProcessInfo, field: TextField) -> Option<&str> {
+    match field {
+        TextField::Name => Some(&process.name),
+        TextField::User => process.user.as_deref(),
+        TextField::EffectiveUser => process.effective_user.as_deref(),
+        TextField::Group => process.group.as_deref(),
+        TextField::EffectiveGroup => process.effective_group.as_deref(),
+        TextField::Status => Some(&process.status),
+        TextField::Command => process.command_line.as_deref(),
+        TextField::Exe => process.exe.as_deref(),
+    }
+}
+
+fn number_of(process: &ProcessInfo, field: NumberField) -> Option<f64> {
+    match field {
+        NumberField::Cpu => Some(process.cpu_usage as f64),
+        NumberField::Memory => Some(process.memory_usage as f64),
+        NumberField::VirtualMemory => Some(process.virtual_memory as f64),
+        NumberField::Pid => Some(process.pid as f64),
+        NumberField::ParentPid => process.parent_pid.map(f64::from),
+        NumberField::Threads => process.threads.map(f64::from),
+        NumberField::Nice => process.nice.map(f64::from),
+        NumberField::DiskRead => Some(process.disk_read_per_sec),
+        NumberField::DiskWrite => Some(process.disk_write_per_sec),
+    }
+}
+
+// "1.5G", "300", "20kb"; plain numbers are megabytes, like the memory column
+fn parse_size(text: &str) -> Option<f64> {
+    let split = text.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(text.len());
+    let value: f64 = text[..split].parse().ok()?;
+    let multiplier = match text[split..].to_lowercase().as_str() {
+        "b" => 1.0,
+        "k" | "kb" => 1024.0,
+        "" | "m" | "mb" => 1024.0 * 1024.0,
+        "g" | "gb" => 1024.0 * 1024.0 * 1024.0,
+        "t" | "tb" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(value * multiplier)
+}
+
+fn regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("(?i){}", pattern)).map_err(|e| match e {
+        regex::Error::Syntax(message) => format!(
+            "invalid regular expression /{}/: {}",
+            pattern,
+            message.lines().last().unwrap_or_default().trim_start_matches("error: ")
+        ),
+        e => format!("invalid regular expression /{}/: {}", pattern, e),
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, at: usize, message: impl std::fmt::Display) -> String {
+        format!("column {}: {}", at + 1, message)
+    }
+
+    // Text up to the closing delimiter, with backslash escaping the delimiter
+    fn delimited(&mut self, delimiter: char) -> Result<String, String> {
+        let start = self.position;
+        self.position += 1;
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.position += 1;
+            match c {
+                '\\' if self.peek() == Some(delimiter) => {
+                    text.push(delimiter);
+                    self.position += 1;
+                }
+                c if c == delimiter => return Ok(text),
+                c => text.push(c),
+            }
+        }
+        Err(self.error(start, format!("missing closing {}", delimiter)))
+    }
+
+    // A quoted or plain value, ending at whitespace
+    fn value(&mut self) -> Result<String, String> {
+        if self.peek() == Some('"') {
+            return self.delimited('"');
+        }
+        let start = self.position;
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            self.position += 1;
+        }
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    fn operator(&mut self) -> Option<Operator> {
+        let (operator, length) = match (self.peek()?, self.chars.get(self.position + 1)) {
+            ('>', Some('=')) => (Operator::GreaterOrEqual, 2),
+            ('<', Some('=')) => (Operator::LessOrEqual, 2),
+            ('>', _) => (Operator::Greater, 1),
+            ('<', _) => (Operator::Less, 1),
+            ('=', _) => (Operator::Equal, 1),
+            (':', _) => (Operator::Contains, 1),
+            ('~', _) => (Operator::Regex, 1),
+            _ => return None,
+        };
+        self.position += length;
+        Some(operator)
+    }
+
+    fn term(&mut self) -> Result<Option<Term>, String> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        let Some(first) = self.peek() else {
+            return Ok(None);
+        };
+        let negated = matches!(first, '-' | '!')
+            && self.chars.get(self.position + 1).is_some_and(|c| !c.is_whitespace());
+        if negated {
+            self.position += 1;
+        }
+        let start = self.position;
+
+        let condition = match self.peek() {
+            Some('"') => Condition::Contains(TextField::Name, self.delimited('"')?.to_lowercase()),
+            Some('/') => Condition::Matches(TextField::Name, regex(&self.delimited('/')?).map_err(|e| self.error(start, e))?),
+            _ => {
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                let operator = if name.is_empty() { None } else { self.operator() };
+                match operator {
+                    Some(operator) => self.condition(start, &name, operator)?,
+                    None => {
+                        // A plain word, possibly with punctuation in it
+                        self.position = start;
+                        Condition::Contains(TextField::Name, self.value()?.to_lowercase())
+                    }
+                }
+            }
+        };
+        Ok(Some(Term { negated, condition }))
+    }
+
+    fn condition(&mut self, start: usize, name: &str, operator: Operator) -> Result<Condition, String> {
+        let field = field(name).ok_or_else(|| self.error(start, format!("unknown field `{}`", name)))?;
+        let value_start = self.position;
+        let value = if operator == Operator::Regex && self.peek() == Some('/') {
+            self.delimited('/')?
+        } else {
+            self.value()?
+        };
+        if value.is_empty() {
+            return Err(self.error(value_start, format!("`{}` needs a value", name)));
+        }
+
+        match (field, operator) {
+            (Field::Text(field), Operator::Contains) => Ok(Condition::Contains(field, value.to_lowercase())),
+            (Field::Text(field), Operator::Equal) => Ok(Condition::Equals(field, value.to_lowercase())),
+            (Field::Text(field), Operator::Regex) => {
+                Ok(Condition::Matches(field, regex(&value).map_err(|e| self.error(value_start, e))?))
+            }
+            (Field::Text(_), _) => Err(self.error(start, format!("`{}` is text, use {}: {}= or {}~", name, name, name, name))),
+            (Field::Number(_), Operator::Regex) => Err(self.error(start, format!("`{}` is a number, use > < >= <= or =", name))),
+            (Field::Number(field), operator) => {
+                let number = if field.is_size() {
+                    parse_size(&value)
+                } else {
+                    value.trim_end_matches('%').parse().ok()
+                };
+                let number = number.ok_or_else(|| self.error(value_start, format!("`{}` is not a {}", value,
+                    if field.is_size() { "size such as 500M or 1.5G" } else { "number" })))?;
+                let operator = if operator == Operator::Contains { Operator::Equal } else { operator };
+                Ok(Condition::Compare(field, operator, number))
+            }
+        }
+    }
+}
+
+impl ResourceMonitor {
+    // Re-parses the search box if its text changed and updates the table right away,
+    // without waiting for the next sample
+    pub(crate) fn apply_filter(&mut self) {
+        if self.parsed_query != self.process_filter {
+            self.parsed_query = self.process_filter.clone();
+            self.filter = ProcessFilter::parse(&self.process_filter);
+        }
+        self.update_view();
+    }
+
+    pub(crate) fn set_filter(&mut self, query: String) {
+        self.process_filter = query;
+        self.apply_filter();
+    }
+
+    // A query with errors filters nothing until it is fixed
+    pub(crate) fn matches_filter(&self, process: &ProcessInfo) -> bool {
+        self.filter.as_ref().map_or(true, |filter| filter.matches(process))
+    }
+
+    pub(crate) fn filter_error(&self) -> Option<&str> {
+        self.filter.as_ref().err().map(String::as_str)
+    }
+
+    fn save_filters(&mut self) {
+        if let Err(e) = storage::save_json(FILTERS_FILE, &self.saved_filters) {
+            eprintln!("Error saving filters: {}", e);
+        }
+    }
+
+    // Contents of the "Filters" menu next to the search box
+    pub(crate) fn render_saved_filters(&mut self, ui: &mut egui::Ui) {
+        let mut apply = None;
+        let mut remove = None;
+        if self.saved_filters.is_empty() {
+            ui.label(egui::RichText::new("No saved filters").color(ui.visuals().weak_text_color()));
+        }
+        for (index, saved) in self.saved_filters.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&saved.name).on_hover_text(&saved.query).clicked() {
+                    apply = Some(saved.query.clone());
+                    ui.close_menu();
+                }
+                if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(query) = apply {
+            self.set_filter(query);
+        }
+        if let Some(index) = remove {
+            self.saved_filters.remove(index);
+            self.save_filters();
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_filter_name)
+                .hint_text("Name")
+                .desired_width(120.0));
+            let name = self.new_filter_name.trim().to_string();
+            let can_save = !name.is_empty() && !self.process_filter.trim().is_empty() && self.filter.is_ok();
+            if ui.add_enabled(can_save, egui::Button::new("Save current"))
+                .on_disabled_hover_text("Enter a name and a valid query first")
+                .clicked()
+            {
+                // Saving under an existing name replaces that filter
+                let saved = SavedFilter { name: name.clone(), query: self.process_filter.clone() };
+                match self.saved_filters.iter_mut().find(|f| f.name == name) {
+                    Some(existing) => *existing = saved,
+                    None => self.saved_filters.push(saved),
+                }
+                self.new_filter_name.clear();
+                self.save_filters();
+            }
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, pid: u32) -> ProcessInfo {
+        ProcessInfo {
+            name: name.to_string(),
+            pid,
+            cpu_usage: 12.5,
+            memory_usage: 300 * 1024 * 1024,
+            status: "Run".to_string(),
+            user: Some("alice".to_string()),
+            effective_user: Some("root".to_string()),
+            group: Some("staff".to_string()),
+            command_line: Some("python3 -m http.server --port 5432".to_string()),
+            parent_pid: Some(1),
+            threads: Some(8),
+            nice: Some(-5),
+            disk_read_per_sec: 2.0 * 1024.0 * 1024.0,
+            exe: Some("/usr/bin/python3".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn matches(query: &str, process: &ProcessInfo) -> bool {
+        ProcessFilter::parse(query).unwrap_or_else(|e| panic!("{}: {}", query, e)).matches(process)
+    }
+
+    fn error(query: &str) -> String {
+        match ProcessFilter::parse(query) {
+            Ok(_) => panic!("{} parsed", query),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn empty_queries_match_everything() {
+        let python = process("python3", 100);
+        assert!(matches("", &python));
+        assert!(matches("   ", &python));
+    }
+
+    #[test]
+    fn words_match_the_name() {
+        let python = process("python3", 100);
+        assert!(matches("PYTH", &python));
+        // Every word has to occur, in any order
+        assert!(matches("on3 pyth", &python));
+        assert!(!matches("pyth bash", &python));
+        assert!(matches("\"on 3\"", &process("python 3", 1)));
+        assert!(!matches("\"on 3\"", &python));
+    }
+
+    #[test]
+    fn text_fields() {
+        let python = process("python3", 100);
+        assert!(matches("user:ali", &python));
+        assert!(!matches("user=ali", &python));
+        assert!(matches("user=ALICE", &python));
+        assert!(matches("euser=root group:staff", &python));
+        assert!(!matches("egroup:staff", &python), "missing values never match");
+        assert!(matches("status:run cmd:http.server exe=/usr/bin/python3", &python));
+        assert!(matches("command:server path:bin", &python));
+    }
+
+    #[test]
+    fn numeric_fields() {
+        let python = process("python3", 100);
+        assert!(matches("cpu>10 cpu<=12.5 cpu>=12.5%", &python));
+        assert!(!matches("cpu>12.5", &python));
+        assert!(matches("pid=100 pid:100 ppid=1 threads>=8 nice<0", &python));
+        assert!(!matches("pid>100", &python));
+        assert!(matches("write=0 read>1", &python));
+    }
+
+    #[test]
+    fn sizes_default_to_megabytes() {
+        let python = process("python3", 100);
+        assert!(matches("mem>299 mem<301", &python));
+        assert!(matches("mem>0.2G mem<0.3g mem>307199kb mem<314572801b", &python));
+        assert!(matches("rss=300MB memory=300m", &python));
+        assert!(matches("vmem<1", &python));
+        assert!(matches("read>=2M read<=2048K", &python));
+        assert!(!matches("mem>1T", &python));
+    }
+
+    #[test]
+    fn negation() {
+        let python = process("python3", 100);
+        assert!(!matches("-python", &python));
+        assert!(!matches("!user:alice", &python));
+        assert!(matches("-cpu>50 !pid=1", &python));
+        // A lone dash is a word, not a negation
+        assert!(!matches("- python", &python));
+        assert!(matches("-", &process("a-b", 1)));
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let python = process("python3", 100);
+        assert!(matches("/^py.*\\d$/", &python));
+        assert!(matches("/PYTHON/", &python), "case-insensitive");
+        assert!(!matches("/^thon/", &python));
+        assert!(matches("cmd~\"--port 54\\d\\d\"", &python));
+        assert!(matches("exe~/bin\\/py/", &python));
+        assert!(matches("name~^py", &python));
+        assert!(!matches("-/py/", &python));
+        assert!(matches("/a\\/b/", &process("a/b", 1)));
+    }
+
+    #[test]
+    fn quoted_values() {
+        let python = process("python3", 100);
+        assert!(matches("cmd:\"http.server --port\"", &python));
+        assert!(!matches("cmd:\"http.server  --port\"", &python));
+        assert!(matches("user=\"al\\\"ice\"", &process_with_user("al\"ice")));
+    }
+
+    fn process_with_user(user: &str) -> ProcessInfo {
+        ProcessInfo { user: Some(user.to_string()), ..process("p", 1) }
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(error("python colour:red"), "column 8: unknown field `colour`");
+        assert_eq!(error("cpu>"), "column 5: `cpu` needs a value");
+        assert_eq!(error("cpu>lots"), "column 5: `lots` is not a number");
+        assert_eq!(error("mem>5 mem<1X"), "column 11: `1X` is not a size such as 500M or 1.5G");
+        assert_eq!(error("user>3"), "column 1: `user` is text, use user: user= or user~");
+        assert_eq!(error("-cpu~5"), "column 2: `cpu` is a number, use > < >= <= or =");
+        assert_eq!(error("a \"open"), "column 3: missing closing \"");
+        assert_eq!(error("/open"), "column 1: missing closing /");
+        assert!(error("name~\"(\"").starts_with("column 6: invalid regular expression /(/: "));
+        assert!(error("/[a-/").starts_with("column 1: invalid regular expression /[a-/: "));
+    }
+
+    #[test]
+    fn apply_filter_parses_only_changed_text() {
+        let mut monitor = ResourceMonitor::new();
+        monitor.processes = vec![process("python3", 100), process("bash", 200)];
+
+        monitor.set_filter("bash".to_string());
+        assert_eq!(monitor.process_indices, [1]);
+
+        // Same text: the parsed query is reused, only the view is rebuilt
+        monitor.filter = Ok(ProcessFilter::parse("python").unwrap());
+        monitor.apply_filter();
+        assert_eq!(monitor.process_indices, [0]);
+
+        // New text is parsed again; a broken query hides nothing
+        monitor.set_filter("cpu>".to_string());
+        assert_eq!(monitor.filter_error(), Some("column 5: `cpu` needs a value"));
+        assert_eq!(monitor.process_indices.len(), 2);
+
+        monitor.set_filter("cpu>1 -pyth".to_string());
+        assert_eq!(monitor.filter_error(), None);
+        assert_eq!(monitor.process_indices, [1]);
+    }
+}
@@ -0,0 +1,199 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::columns::TableColumn;
+use crate::theme::color;
+use crate::{ProcessInfo, ResourceMonitor};
+
+// Room left of a grouped process's name, so it reads as part of the group above
+pub const MEMBER_INDENT: f32 = 18.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    #[default]
+    None,
+    Name,
+    User,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 3] = [GroupBy::None, GroupBy::Name, GroupBy::User];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupBy::None => "No grouping",
+            GroupBy::Name => "Group by name",
+            GroupBy::User => "Group by user",
+        }
+    }
+
+    fn key(&self, process: &ProcessInfo) -> String {
+        match self {
+            GroupBy::User => process.user.clone().unwrap_or_else(|| "N/A".to_string()),
+            _ => process.name.clone(),
+        }
+    }
+}
+
+// Processes sharing a name or user, with their totals
+pub struct ProcessGroup {
+    pub key: String,
+    // Indices into `processes`, in table order
+    pub members: Vec<usize>,
+    // Sums of the members' usage, earliest start and longest run time; sorted and painted like a process
+    pub total: ProcessInfo,
+}
+
+impl ProcessGroup {
+    fn new(key: String) -> Self {
+        let total = ProcessInfo { name: key.clone(), ..Default::default() };
+        Self { key, members: Vec::new(), total }
+    }
+
+    fn add(&mut self, index: usize, process: &ProcessInfo) {
+        let total = &mut self.total;
+        if self.members.is_empty() {
+            total.user = process.user.clone();
+            total.start_time = process.start_time;
+        } else if total.user != process.user {
+            total.user = None;
+        }
+        total.cpu_usage += process.cpu_usage;
+        total.memory_usage += process.memory_usage;
+        total.virtual_memory += process.virtual_memory;
+        total.threads = match (total.threads, process.threads) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        total.disk_read_per_sec += process.disk_read_per_sec;
+        total.disk_write_per_sec += process.disk_write_per_sec;
+        if process.start_time != 0 && (total.start_time == 0 || process.start_time < total.start_time) {
+            total.start_time = process.start_time;
+        }
+        total.run_time = total.run_time.max(process.run_time);
+        self.members.push(index);
+    }
+}
+
+// One line of the process table
+#[derive(Clone, Copy, PartialEq)]
+pub enum TableRow {
+    Process(usize),
+    // A collapsible aggregate, an index into `groups`
+    Group(usize),
+    // A process shown under its expanded group
+    Member(usize),
+}
+
+impl TableRow {
+    pub fn process(&self) -> Option<usize> {
+        match self {
+            TableRow::Process(index) | TableRow::Member(index) => Some(*index),
+            TableRow::Group(_) => None,
+        }
+    }
+}
+
+impl ResourceMonitor {
+    // Turns the sorted, filtered processes into table rows. Groups are sorted on their totals
+    // and keep their members in table order; a lone process is shown without a group.
+    pub(crate) fn build_table_rows(&mut self) {
+        self.table_rows.clear();
+        if self.group_by == GroupBy::None {
+            self.table_rows.extend(self.process_indices.iter().map(|&i| TableRow::Process(i)));
+            self.groups.clear();
+            return;
+        }
+
+        let mut groups: Vec<ProcessGroup> = Vec::new();
+        let mut by_key: HashMap<String, usize> = HashMap::new();
+        for &index in &self.process_indices {
+            let process = &self.processes[index];
+            let key = self.group_by.key(process);
+            let group = *by_key.entry(key.clone()).or_insert_with(|| {
+                groups.push(ProcessGroup::new(key));
+                groups.len() - 1
+            });
+            groups[group].add(index, process);
+        }
+
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.compare_rows(&groups[a].total, &groups[b].total)
+                .then_with(|| groups[a].key.cmp(&groups[b].key))
+        });
+        for group in order {
+            match groups[group].members.as_slice() {
+                [only] => self.table_rows.push(TableRow::Process(*only)),
+                members => {
+                    self.table_rows.push(TableRow::Group(group));
+                    if self.expanded_groups.contains(&groups[group].key) {
+                        self.table_rows.extend(members.iter().map(|&i| TableRow::Member(i)));
+                    }
+                }
+            }
+        }
+        self.groups = groups;
+    }
+
+    pub(crate) fn set_group_by(&mut self, group_by: GroupBy) {
+        self.group_by = group_by;
+        self.expanded_groups.clear();
+        self.build_table_rows();
+    }
+
+    pub(crate) fn toggle_group(&mut self, group: usize) {
+        let Some(key) = self.groups.get(group).map(|g| g.key.clone()) else {
+            return;
+        };
+        if !self.expanded_groups.remove(&key) {
+            self.expanded_groups.insert(key);
+        }
+        self.build_table_rows();
+    }
+
+    pub(crate) fn expand_all_groups(&mut self, expand: bool) {
+        self.expanded_groups.clear();
+        if expand {
+            self.expanded_groups.extend(self.groups.iter().map(|g| g.key.clone()));
+        }
+        self.build_table_rows();
+    }
+
+    // Text of a group's cell; columns that don't add up across processes stay empty
+    fn group_cell(&self, column: TableColumn, group: &ProcessGroup) -> Option<(String, egui::Color32)> {
+        match column {
+            TableColumn::Name => {
+                let arrow = if self.expanded_groups.contains(&group.key) { "▼" } else { "▶" };
+                Some((format!("{} {} ({})", arrow, group.key, group.members.len()), color(self.palette.text)))
+            }
+            TableColumn::Pid | TableColumn::ParentPid | TableColumn::Status | TableColumn::Nice
+                | TableColumn::EffectiveUser | TableColumn::Group | TableColumn::EffectiveGroup
+                | TableColumn::CommandLine | TableColumn::Exe => None,
+            TableColumn::User if group.total.user.is_none() => None,
+            column => Some(self.cell(column, &group.total)),
+        }
+    }
+
+    // An aggregate row; returns (hovered, clicked)
+    pub(crate) fn render_group_row(&self, ui: &mut egui::Ui, group: &ProcessGroup, row_index: usize) -> (bool, bool) {
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width().max(self.table_width()), self.row_height + 4.0),
+            egui::Sense::click(),
+        );
+        let rect = response.rect;
+        let background = if row_index % 2 == 0 { self.palette.row_even } else { self.palette.row_odd };
+        painter.rect_filled(rect, 0.0, color(background));
+        if response.hovered() {
+            painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, color(self.palette.hover)));
+        }
+
+        let font_id = egui::TextStyle::Body.resolve(ui.style());
+        self.paint_cells(&painter, rect, &font_id, 0.0, |column| self.group_cell(column, group));
+        let response = response.on_hover_text("Click to show or hide the processes");
+        (response.hovered(), response.clicked())
+    }
+}
+
@@ -1,27 +1,102 @@
-#![windows_subsystem = "windows"]
+// Release builds start without a console window; see attach_parent_console for command line use
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use eframe::egui;
-use egui_plot::{Line, Legend, Plot};
-use sysinfo::{System, Pid, RefreshKind, ProcessRefreshKind, CpuRefreshKind, MemoryRefreshKind, Disks};
+use egui_plot::{Line, Legend, Plot, VLine};
+use sysinfo::{System, Pid, RefreshKind, ProcessRefreshKind, CpuRefreshKind, MemoryRefreshKind, Disks, Networks};
 use serde::{Serialize, Deserialize};
-use chrono::Local;
-use std::collections::VecDeque;
+use chrono::{Local, TimeZone};
+use std::collections::{HashSet, VecDeque};
 
-#[derive(Serialize, Deserialize, Clone)]
+mod actions;
+mod alerts;
+mod audit;
+mod columns;
+mod commands;
+mod compare;
+mod config;
+mod export;
+mod filter;
+mod grouping;
+mod metrics;
+mod notifications;
+mod settings;
+mod shortcuts;
+mod sinks;
+mod snapshot;
+mod snapshot_log;
+mod storage;
+mod theme;
+mod thresholds;
+mod users;
+
+use alerts::{AlertConfig, AlertEngine, AlertsTab};
+use audit::AuditEntry;
+use columns::{SortKey, TableColumn};
+use compare::CompareView;
+use export::{ExportFormat, ExportScope};
+use filter::{ProcessFilter, SavedFilter};
+use grouping::{GroupBy, ProcessGroup, TableRow};
+use metrics::MetricsServer;
+use notifications::Notifier;
+use settings::Settings;
+use shortcuts::KeyBindings;
+use sinks::{Sink, SinkConfig};
+use snapshot::Snapshot;
+use snapshot_log::{SnapshotLog, SnapshotLogConfig};
+use theme::{color, Palette, ThemeSettings};
+use thresholds::ColorThresholds;
+use users::UserNames;
+
+// Missing fields default so that exports with a subset of columns can be opened again
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 struct ProcessInfo {
     name: String,
     pid: u32,
     cpu_usage: f32,
     memory_usage: u64,
     status: String,
+    // Real user; the effective ones differ for setuid programs
     user: Option<String>,
+    effective_user: Option<String>,
+    group: Option<String>,
+    effective_group: Option<String>,
     command_line: Option<String>,
+    // Seconds since the Unix epoch; 0 when unknown (older snapshots)
+    start_time: u64,
+    parent_pid: Option<u32>,
+    // Only known on Linux
+    threads: Option<u32>,
+    virtual_memory: u64,
+    nice: Option<i32>,
+    // Seconds
+    run_time: u64,
+    // Averaged over the last update interval
+    disk_read_per_sec: f64,
+    disk_write_per_sec: f64,
+    exe: Option<String>,
+}
+
+impl ProcessInfo {
+    fn key(&self) -> ProcessKey {
+        ProcessKey { pid: self.pid, start_time: self.start_time }
+    }
+}
+
+// Identifies a process across refreshes; the start time tells apart processes that reuse a PID
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ProcessKey {
+    pid: u32,
+    start_time: u64,
 }
 
 // Structure for storing disk information
-#[derive(Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 struct DiskInfo {
     name: String,
+    #[serde(default)]
+    mount_point: String,
     total_space: u64,
     available_space: u64,
     used_space: u64,
@@ -30,34 +105,204 @@ struct DiskInfo {
     file_system: String,
 }
 
-// Structure for storing column widths
-struct ColumnWidths {
-    name: f32,
-    cpu: f32,
-    memory: f32,
-    status: f32,
-    user: f32,
+// Per-interface traffic, rates are averaged over the last update interval
+#[derive(Serialize, Deserialize, Clone)]
+struct NetworkInfo {
+    name: String,
+    received_per_sec: f64,
+    transmitted_per_sec: f64,
+    total_received: u64,
+    total_transmitted: u64,
+}
+
+// Machine-wide totals at the moment of an update
+#[derive(Serialize, Deserialize, Clone)]
+struct SystemTotals {
+    timestamp: f64,
+    cpu_usage: f32,
+    total_memory: u64,
+    used_memory: u64,
+    free_memory: u64,
+    total_swap: u64,
+    used_swap: u64,
+    process_count: usize,
+    uptime: u64,
+}
+
+// Lightweight copy of a process kept with each history sample
+#[derive(Serialize, Deserialize, Clone)]
+struct ProcessSample {
+    name: String,
+    pid: u32,
+    cpu_usage: f32,
+    memory_usage: u64,
+}
+
+// One point of the CPU/memory history
+#[derive(Serialize, Deserialize, Clone)]
+struct HistorySample {
+    timestamp: f64,
+    cpu_usage: f64,
+    memory_mb: f64,
+    top_cpu: Vec<ProcessSample>,
+    top_memory: Vec<ProcessSample>,
+}
+
+// Time window shown by the charts
+#[derive(Clone, Copy, PartialEq)]
+enum ChartRange {
+    LastMinute,
+    Last5Minutes,
+    LastHour,
+    Last24Hours,
+    Custom,
 }
 
+impl ChartRange {
+    const ALL: [ChartRange; 5] = [
+        ChartRange::LastMinute,
+        ChartRange::Last5Minutes,
+        ChartRange::LastHour,
+        ChartRange::Last24Hours,
+        ChartRange::Custom,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ChartRange::LastMinute => "1m",
+            ChartRange::Last5Minutes => "5m",
+            ChartRange::LastHour => "1h",
+            ChartRange::Last24Hours => "24h",
+            ChartRange::Custom => "Custom",
+        }
+    }
+
+    fn seconds(&self, custom_minutes: f32) -> f64 {
+        match self {
+            ChartRange::LastMinute => 60.0,
+            ChartRange::Last5Minutes => 5.0 * 60.0,
+            ChartRange::LastHour => 60.0 * 60.0,
+            ChartRange::Last24Hours => 24.0 * 60.0 * 60.0,
+            ChartRange::Custom => custom_minutes as f64 * 60.0,
+        }
+    }
+}
+
+// Disks are re-read at least this often even when updates are frequent
+const DISK_REFRESH_SECS: f32 = 10.0;
+// Upper bound on points drawn per chart line
+const MAX_CHART_POINTS: usize = 2000;
+// Upper bound on history samples kept, whatever the retention and interval
+const MAX_HISTORY_POINTS: usize = 20_000;
+
 struct ResourceMonitor {
     system: System,
     processes: Vec<ProcessInfo>,
     disks: Vec<DiskInfo>,
+    last_disk_update: std::time::Instant,
+    user_names: UserNames,
+    networks: Networks,
+    network_info: Vec<NetworkInfo>,
     process_indices: Vec<usize>,
-    sort_column: SortColumn,
+    // What the table shows: process_indices, or groups of them when grouping
+    table_rows: Vec<TableRow>,
+    group_by: GroupBy,
+    groups: Vec<ProcessGroup>,
+    expanded_groups: HashSet<String>,
+    sort_column: TableColumn,
     sort_descending: bool,
-    selected_pid: Option<u32>,
+    // Keys after the primary one, added by shift-clicking headers
+    then_by: Vec<SortKey>,
+    selected: Option<ProcessKey>,
     update_interval: f32,
     last_update: std::time::Instant,
-    history: VecDeque<(f64, f64)>,
+    history: VecDeque<HistorySample>,
     max_history_points: usize,
+    history_retention_secs: f64,
+    top_processes_per_sample: usize,
     show_system_info: bool,
     show_disk_info: bool,
+    show_users: bool,
     process_filter: String,
+    // Parsed form of process_filter, and the text it was parsed from
+    filter: Result<ProcessFilter, String>,
+    parsed_query: String,
+    saved_filters: Vec<SavedFilter>,
+    new_filter_name: String,
     show_charts: bool,
     row_height: f32,
-    hovered_row: Option<usize>,
+    hovered: Option<ProcessKey>,
     energy_saving_mode: bool,
+    chart_range: ChartRange,
+    chart_custom_minutes: f32,
+    chart_reset: bool,
+    chart_hover_time: Option<f64>,
+    selected_sample_time: Option<f64>,
+    show_export_window: bool,
+    export_format: ExportFormat,
+    export_scope: ExportScope,
+    export_columns: Vec<TableColumn>,
+    export_status: Option<(String, bool)>,
+    snapshot: Option<Snapshot>,
+    snapshot_error: Option<String>,
+    compare: CompareView,
+    metrics_server: Option<MetricsServer>,
+    metrics_addr: String,
+    metrics_top_n: usize,
+    metrics_error: Option<String>,
+    sink_configs: Vec<SinkConfig>,
+    sinks: Vec<Sink>,
+    show_sinks_window: bool,
+    sinks_message: Option<(String, bool)>,
+    snapshot_log_config: SnapshotLogConfig,
+    snapshot_log: Option<SnapshotLog>,
+    snapshot_log_error: Option<String>,
+    show_snapshot_log_window: bool,
+    alert_config: AlertConfig,
+    alert_engine: AlertEngine,
+    show_alerts_window: bool,
+    alerts_tab: AlertsTab,
+    alerts_message: Option<(String, bool)>,
+    notifiers: Vec<Notifier>,
+    test_notifiers: Vec<Notifier>,
+    recording_until: Option<std::time::Instant>,
+    audit_log: VecDeque<AuditEntry>,
+    // Where audited actions are appended, None when there is no config directory
+    audit_path: Option<std::path::PathBuf>,
+    show_audit_window: bool,
+    saved_settings: Settings,
+    settings_changed_at: Option<std::time::Instant>,
+    // Shown columns in display order
+    visible_columns: Vec<TableColumn>,
+    column_widths: std::collections::BTreeMap<TableColumn, f32>,
+    // Header being dragged to a new place
+    dragged_column: Option<TableColumn>,
+    color_thresholds: ColorThresholds,
+    show_thresholds_window: bool,
+    theme: ThemeSettings,
+    // Colors of the theme currently on screen
+    palette: Palette,
+    applied_theme: Option<(ThemeSettings, Option<eframe::Theme>)>,
+    show_theme_window: bool,
+    key_bindings: KeyBindings,
+    show_shortcuts_window: bool,
+    // Process waiting for the user to confirm its termination
+    confirm_kill: Option<ProcessKey>,
+    type_ahead_buffer: String,
+    type_ahead_at: Option<std::time::Instant>,
+    scroll_to_selected: bool,
+    // Time between the last two process refreshes
+    process_interval_secs: f32,
+    table_scroll_offset: f32,
+    // Row the table scroll position follows: its key, row and the scroll offset it was seen at
+    table_anchor: Option<(ProcessKey, usize, f32)>,
+    // Rows that fit in the table, for PageUp/PageDown
+    table_page_rows: usize,
+    show_command_palette: bool,
+    command_query: String,
+    // Highlighted entry among the palette's matches
+    command_selected: usize,
+    command_palette_opened_frame: u64,
 }
 
 impl Default for ResourceMonitor {
@@ -66,15 +311,6 @@ impl Default for ResourceMonitor {
     }
 }
 
-#[derive(Default, PartialEq)]
-enum SortColumn {
-    #[default]
-    Name,
-    Cpu,
-    Memory,
-    Status,
-}
-
 impl ResourceMonitor {
     fn new() -> Self {
         let mut system = System::new_with_specifics(
@@ -113,6 +349,7 @@ impl ResourceMonitor {
              
             disks_info.push(DiskInfo {
                 name: disk.name().to_string_lossy().into_owned(),
+                mount_point: disk.mount_point().to_string_lossy().into_owned(),
                 total_space,
                 available_space,
                 used_space,
@@ -126,21 +363,98 @@ impl ResourceMonitor {
             system,
             processes: Vec::new(),
             disks: disks_info,
+            last_disk_update: std::time::Instant::now(),
+            user_names: UserNames::load(),
+            networks: Networks::new_with_refreshed_list(),
+            network_info: Vec::new(),
             process_indices: Vec::new(),
-            sort_column: SortColumn::default(),
+            table_rows: Vec::new(),
+            group_by: GroupBy::None,
+            groups: Vec::new(),
+            expanded_groups: HashSet::new(),
+            sort_column: TableColumn::Name,
             sort_descending: false,
-            selected_pid: None,
+            then_by: Vec::new(),
+            selected: None,
             update_interval: 1.0,
             last_update: std::time::Instant::now(),
-            history: VecDeque::with_capacity(100),
-            max_history_points: 100,
+            history: VecDeque::with_capacity(1024),
+            max_history_points: MAX_HISTORY_POINTS,
+            history_retention_secs: 24.0 * 60.0 * 60.0,
+            top_processes_per_sample: 5,
             show_system_info: true,
             show_disk_info: true,
+            show_users: false,
             process_filter: String::new(),
+            filter: Ok(ProcessFilter::default()),
+            parsed_query: String::new(),
+            saved_filters: Vec::new(),
+            new_filter_name: String::new(),
             show_charts: true,
             row_height: 25.0,
-            hovered_row: None,
+            hovered: None,
             energy_saving_mode: false,
+            chart_range: ChartRange::Last5Minutes,
+            chart_custom_minutes: 15.0,
+            chart_reset: false,
+            chart_hover_time: None,
+            selected_sample_time: None,
+            show_export_window: false,
+            export_format: ExportFormat::Json,
+            export_scope: ExportScope::FilteredView,
+            export_columns: TableColumn::ALL.to_vec(),
+            export_status: None,
+            snapshot: None,
+            snapshot_error: None,
+            compare: CompareView::default(),
+            metrics_server: None,
+            metrics_addr: metrics::DEFAULT_METRICS_ADDR.to_string(),
+            metrics_top_n: 10,
+            metrics_error: None,
+            sink_configs: Vec::new(),
+            sinks: Vec::new(),
+            show_sinks_window: false,
+            sinks_message: None,
+            snapshot_log_config: SnapshotLogConfig::default(),
+            snapshot_log: None,
+            snapshot_log_error: None,
+            show_snapshot_log_window: false,
+            alert_config: AlertConfig::default(),
+            alert_engine: AlertEngine::default(),
+            show_alerts_window: false,
+            alerts_tab: AlertsTab::Alerts,
+            alerts_message: None,
+            notifiers: Vec::new(),
+            test_notifiers: Vec::new(),
+            recording_until: None,
+            audit_log: VecDeque::new(),
+            audit_path: storage::config_dir().map(|dir| dir.join(audit::AUDIT_FILE)),
+            show_audit_window: false,
+            saved_settings: Settings::default(),
+            settings_changed_at: None,
+            visible_columns: TableColumn::DEFAULT.to_vec(),
+            column_widths: columns::default_widths(),
+            dragged_column: None,
+            color_thresholds: ColorThresholds::default(),
+            show_thresholds_window: false,
+            theme: ThemeSettings::default(),
+            palette: Palette::default(),
+            applied_theme: None,
+            show_theme_window: false,
+            key_bindings: KeyBindings::default(),
+            show_shortcuts_window: false,
+            confirm_kill: None,
+            type_ahead_buffer: String::new(),
+            type_ahead_at: None,
+            scroll_to_selected: false,
+            process_interval_secs: 1.0,
+            table_scroll_offset: 0.0,
+            table_anchor: None,
+            table_page_rows: 1,
+            show_command_palette: false,
+            command_query: String::new(),
+            command_selected: 0,
+            command_palette_opened_frame: 0,
         }
     }
 
@@ -166,66 +480,153 @@ impl ResourceMonitor {
         return;
     }
     
+    self.refresh();
+    
+    ctx.request_repaint_after(std::time::Duration::from_secs_f32(effective_interval));
+}
+
+    // Collects one sample; shared by the window and headless mode
+    fn refresh(&mut self) {
+    let now = std::time::Instant::now();
+    let time_since_update = now.duration_since(self.last_update).as_secs_f32();
     self.last_update = now;
+    self.process_interval_secs = time_since_update;
     
     self.system.refresh_cpu();
+    self.system.refresh_memory();
     self.system.refresh_processes();
+    self.user_names.refresh_if_stale();
     
-    self.processes.clear();
-    
-    for (pid, process) in self.system.processes() {
-        let name = if process.name().is_empty() {
-            format!("PID: {}", pid.as_u32())
-        } else {
-            process.name().to_string()
-        };
-        
-        if !self.process_filter.is_empty() {
-            let filter_lower = self.process_filter.to_lowercase();
-            if !name.to_lowercase().contains(&filter_lower) {
-                continue;
-            }
-        }
-        
-        // Исправление: используем to_string_lossy() только для OsStr
-        let command_line = process.cmd()
-    .first()
-    .map(|s| {
-        let os_str: &std::ffi::OsStr = s.as_ref();
-        os_str.to_string_lossy().to_string()
-    });
-        
-        let process_info = ProcessInfo {
-            name,
-            pid: pid.as_u32(),
-            cpu_usage: process.cpu_usage(),
-            memory_usage: process.memory(),
-            status: format!("{:?}", process.status()),
-            user: process.user_id().map(|uid| uid.to_string()),
-            command_line,
-        };
-        
-        self.processes.push(process_info);
-    }
-    
-    self.sort_process_indices();
+    // Built once per update and shared by everything that looks at live processes
+    let live_processes = self.all_processes();
     
-    if time_since_update > self.update_interval * 2.0 {
+    if time_since_update > self.update_interval * 2.0
+        || self.last_disk_update.elapsed().as_secs_f32() > DISK_REFRESH_SECS
+    {
         self.update_disk_info();
     }
     
-    let total_cpu = self.system.global_cpu_info().cpu_usage();
-    let used_memory = self.system.used_memory() as f64;
+    self.update_network_info(time_since_update);
+    self.record_history_sample();
+    self.check_alerts(&live_processes);
     
-    if self.history.len() >= self.max_history_points {
-        self.history.pop_front();
+    if self.metrics_server.is_some() || !self.sinks.is_empty() {
+        let snapshot = self.metrics_snapshot(&live_processes);
+        self.publish_metrics(&snapshot);
+        self.push_to_sinks(&snapshot);
     }
-    self.history.push_back((total_cpu as f64, used_memory / 1024.0 / 1024.0));
     
-    ctx.request_repaint_after(std::time::Duration::from_secs_f32(effective_interval));
+    self.append_snapshot_log(&live_processes);
+    self.stop_expired_recording();
+    
+    // An opened snapshot replaces the live process list; history keeps recording.
+    // The list is kept whole, the search filter only decides which rows the table shows.
+    self.processes = match &self.snapshot {
+        Some(snapshot) => snapshot.processes.clone(),
+        None => live_processes,
+    };
+    self.apply_filter();
 }
 
+    fn record_history_sample(&mut self) {
+        let timestamp = unix_timestamp();
+        let total_cpu = self.system.global_cpu_info().cpu_usage();
+        let used_memory = self.system.used_memory() as f64;
+        
+        // Rank every process, not just the filtered ones, so spikes can be attributed later
+        let mut ranked: Vec<ProcessSample> = self.system.processes()
+            .iter()
+            .map(|(pid, process)| ProcessSample {
+                name: if process.name().is_empty() {
+                    format!("PID: {}", pid.as_u32())
+                } else {
+                    process.name().to_string()
+                },
+                pid: pid.as_u32(),
+                cpu_usage: process.cpu_usage(),
+                memory_usage: process.memory(),
+            })
+            .collect();
+        
+        ranked.sort_by(|a, b| b.cpu_usage
+            .partial_cmp(&a.cpu_usage)
+            .unwrap_or(std::cmp::Ordering::Equal));
+        let top_cpu = ranked.iter().take(self.top_processes_per_sample).cloned().collect();
+        
+        ranked.sort_by_key(|p| std::cmp::Reverse(p.memory_usage));
+        ranked.truncate(self.top_processes_per_sample);
+        let top_memory = ranked;
+        
+        self.push_history_sample(HistorySample {
+            timestamp,
+            cpu_usage: total_cpu as f64,
+            memory_mb: used_memory / 1024.0 / 1024.0,
+            top_cpu,
+            top_memory,
+        });
+    }
+
+    fn push_history_sample(&mut self, sample: HistorySample) {
+        let timestamp = sample.timestamp;
+        if let Some(previous) = self.history.back().map(|s| s.timestamp) {
+            self.thin_history(previous, timestamp);
+        }
+        
+        // Drop samples that are too old or exceed the point budget
+        while let Some(oldest) = self.history.front() {
+            if timestamp - oldest.timestamp > self.history_retention_secs
+                || self.history.len() >= self.max_history_points
+            {
+                self.history.pop_front();
+            } else {
+                break;
+            }
+        }
+        
+        self.history.push_back(sample);
+    }
+
+    // When the retention holds more updates than the point budget, the newest half of the budget keeps
+    // every sample and older ones are thinned as they age past it, spread evenly over the rest of the
+    // retention. Only samples that crossed that line since the previous update are looked at.
+    fn thin_history(&mut self, previous: f64, now: f64) {
+        let recent_points = self.max_history_points / 2;
+        let recent_secs = recent_points as f64 * self.update_interval as f64;
+        // The recent window holds one more sample than it has intervals, and a new one is about to be added
+        let older_points = self.max_history_points.saturating_sub(recent_points + 2).max(1);
+        let spacing = (self.history_retention_secs - recent_secs) / older_points as f64;
+        if spacing <= self.update_interval as f64 {
+            return;
+        }
+        
+        let start = self.history.partition_point(|s| s.timestamp < previous - recent_secs).max(1);
+        let mut end = self.history.partition_point(|s| s.timestamp < now - recent_secs);
+        let mut index = start;
+        while index < end {
+            if self.history[index].timestamp - self.history[index - 1].timestamp < spacing {
+                self.history.remove(index);
+                end -= 1;
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    // Returns the history sample closest to the given time
+    fn sample_near(&self, timestamp: f64) -> Option<&HistorySample> {
+        let idx = self.history.partition_point(|s| s.timestamp < timestamp);
+        let after = self.history.get(idx);
+        let before = idx.checked_sub(1).and_then(|i| self.history.get(i));
+        match (before, after) {
+            (Some(b), Some(a)) => {
+                if timestamp - b.timestamp <= a.timestamp - timestamp { Some(b) } else { Some(a) }
+            }
+            (b, a) => b.or(a),
+        }
+    }
+
     fn update_disk_info(&mut self) {
+        self.last_disk_update = std::time::Instant::now();
         self.disks.clear();
         let disks = Disks::new_with_refreshed_list();
         
@@ -249,6 +650,7 @@ impl ResourceMonitor {
             
             self.disks.push(DiskInfo {
                 name: disk.name().to_string_lossy().into_owned(),
+                mount_point: disk.mount_point().to_string_lossy().into_owned(),
                 total_space,
                 available_space,
                 used_space,
@@ -259,65 +661,150 @@ impl ResourceMonitor {
         }
     }
 
+    fn update_network_info(&mut self, elapsed_secs: f32) {
+        self.networks.refresh_list();
+        let elapsed = (elapsed_secs as f64).max(0.001);
+        
+        self.network_info = self.networks
+            .iter()
+            .map(|(name, data)| NetworkInfo {
+                name: name.clone(),
+                received_per_sec: data.received() as f64 / elapsed,
+                transmitted_per_sec: data.transmitted() as f64 / elapsed,
+                total_received: data.total_received(),
+                total_transmitted: data.total_transmitted(),
+            })
+            .collect();
+        self.network_info.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    // Rebuilds the table rows: the processes matching the search, in sort order
+    fn update_view(&mut self) {
+        self.process_indices = (0..self.processes.len())
+            .filter(|&i| self.matches_filter(&self.processes[i]))
+            .collect();
+        self.sort_process_indices();
+    }
+
     fn sort_process_indices(&mut self) {
-        if self.process_indices.len() != self.processes.len() {
-            self.process_indices = (0..self.processes.len()).collect();
+        let mut indices = std::mem::take(&mut self.process_indices);
+        indices.sort_by(|&a, &b| self.compare_rows(&self.processes[a], &self.processes[b]));
+        self.process_indices = indices;
+        self.build_table_rows();
+    }
+
+    // Table order: every sort key in turn, then PID and start time, so rows that tie on the keys
+    // keep the same order from one update to the next
+    fn compare_rows(&self, a: &ProcessInfo, b: &ProcessInfo) -> std::cmp::Ordering {
+        self.sort_keys()
+            .map(|key| {
+                let cmp = key.column.compare(a, b);
+                if key.descending { cmp.reverse() } else { cmp }
+            })
+            .find(|cmp| cmp.is_ne())
+            .unwrap_or_else(|| a.pid.cmp(&b.pid).then(a.start_time.cmp(&b.start_time)))
+    }
+
+    // Sorts by a column, starting in the given direction
+    fn sort_by(&mut self, column: TableColumn, descending: bool) {
+        self.then_by.clear();
+        self.sort_column = column;
+        self.sort_descending = descending;
+        self.sort_process_indices();
+    }
+
+    // Row of a process in the table's current order; None when filtered out or in a collapsed group
+    fn row_of(&self, key: ProcessKey) -> Option<usize> {
+        self.table_rows.iter().position(|row| row.process().is_some_and(|i| self.processes[i].key() == key))
+    }
+
+    fn system_totals(&self) -> SystemTotals {
+        SystemTotals {
+            timestamp: unix_timestamp(),
+            cpu_usage: self.system.global_cpu_info().cpu_usage(),
+            total_memory: self.system.total_memory(),
+            used_memory: self.system.used_memory(),
+            free_memory: self.system.free_memory(),
+            total_swap: self.system.total_swap(),
+            used_swap: self.system.used_swap(),
+            process_count: self.system.processes().len(),
+            uptime: System::uptime(),
         }
+    }
+
+    // Every running process, ignoring the search filter
+    fn all_processes(&self) -> Vec<ProcessInfo> {
+        self.system.processes()
+            .iter()
+            .map(|(pid, process)| process_info(*pid, process, self.process_interval_secs, &self.user_names))
+            .collect()
+    }
+
+    fn open_snapshot(&mut self) {
+        let dialog = rfd::FileDialog::new()
+            .add_filter("Snapshot", &["json", "csv"]);
         
-        match self.sort_column {
-            SortColumn::Name => {
-                self.process_indices.sort_by(|&a, &b| {
-                    let cmp = self.processes[a].name.cmp(&self.processes[b].name);
-                    if self.sort_descending { cmp.reverse() } else { cmp }
-                });
-            }
-            SortColumn::Cpu => {
-                self.process_indices.sort_by(|&a, &b| {
-                    let cmp = self.processes[a].cpu_usage
-                        .partial_cmp(&self.processes[b].cpu_usage)
-                        .unwrap_or(std::cmp::Ordering::Equal);
-                    if self.sort_descending { cmp.reverse() } else { cmp }
-                });
-            }
-            SortColumn::Memory => {
-                self.process_indices.sort_by(|&a, &b| {
-                    let cmp = self.processes[a].memory_usage.cmp(&self.processes[b].memory_usage);
-                    if self.sort_descending { cmp.reverse() } else { cmp }
-                });
-            }
-            SortColumn::Status => {
-                self.process_indices.sort_by(|&a, &b| {
-                    let cmp = self.processes[a].status.cmp(&self.processes[b].status);
-                    if self.sort_descending { cmp.reverse() } else { cmp }
-                });
+        if let Some(path) = dialog.pick_file() {
+            match Snapshot::load(&path) {
+                Ok(snapshot) => {
+                    self.snapshot = Some(snapshot);
+                    self.snapshot_error = None;
+                    self.selected = None;
+                    self.request_immediate_update();
+                }
+                Err(e) => {
+                    eprintln!("Error reading snapshot {}: {}", path.display(), e);
+                    self.snapshot_error = Some(format!("Could not open {}: {}", path.display(), e));
+                }
             }
         }
     }
 
+    fn close_snapshot(&mut self) {
+        self.snapshot = None;
+        self.selected = None;
+        self.request_immediate_update();
+    }
+
+    // Makes the next frame collect data regardless of the interval
+    fn request_immediate_update(&mut self) {
+        self.last_update = std::time::Instant::now() - 
+            std::time::Duration::from_secs_f32(self.update_interval * 5.0);
+    }
+
     fn kill_selected_process(&mut self) {
-        if let Some(pid) = self.selected_pid {
-            if let Some(process) = self.system.process(Pid::from_u32(pid)) {
-                if process.kill() {
-                    println!("Process {} killed", pid);
-                }
-            }
+        if let Some(key) = self.selected {
+            self.kill_process(key);
         }
     }
 
-    fn calculate_column_widths(&self, available_width: f32) -> ColumnWidths {
-        ColumnWidths {
-            name: available_width * 0.35,
-            cpu: available_width * 0.15,
-            memory: available_width * 0.20,
-            status: available_width * 0.15,
-            user: available_width * 0.15,
+    fn kill_process(&mut self, key: ProcessKey) {
+        if self.snapshot.is_some() {
+            return;
+        }
+        let pid = key.pid;
+        let target = actions::describe_process(&self.system, pid);
+        let start_time = self.system.process(Pid::from_u32(pid)).map(|p| p.start_time());
+        // The process may have exited and its PID been handed to a new one since it was selected
+        let result = if start_time.is_some_and(|start| start != key.start_time) {
+            Err("process exited, its PID now belongs to another process".to_string())
+        } else {
+            actions::kill_process(&self.system, pid)
+        };
+        if result.is_ok() {
+            println!("Process {} killed", pid);
         }
+        self.record_audit(AuditEntry::new("manual", "Kill process", &target, false, result));
     }
+
 }
 
 impl eframe::App for ResourceMonitor {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let system_theme = frame.info().system_theme;
+        self.apply_theme(ctx, system_theme);
         self.update(ctx);
+        self.handle_shortcuts(ctx);
         
         egui::TopBottomPanel::top("menu_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -336,23 +823,102 @@ impl eframe::App for ResourceMonitor {
                 
                 ui.checkbox(&mut self.show_system_info, "System information");
                 ui.checkbox(&mut self.show_disk_info, "Disk information");
+                ui.checkbox(&mut self.show_users, "Users");
                 ui.checkbox(&mut self.show_charts, "Charts");
                 ui.checkbox(&mut self.energy_saving_mode, "Energy saving");
                 
                 ui.label("Row height: ");
                 ui.add(egui::Slider::new(&mut self.row_height, 20.0..=40.0));
                 
-                if ui.button("Export JSON").clicked() {
-                    if let Ok(json) = serde_json::to_string_pretty(&self.processes) {
-                        let filename = format!("processes_{}.json", 
-                            Local::now().format("%Y%m%d_%H%M%S"));
-                        if let Err(e) = std::fs::write(&filename, json) {
-                            eprintln!("Error writing file: {}", e);
-                        } else {
-                            ui.add(egui::Label::new(egui::RichText::new(format!("Saved to {}", filename))
-                                .color(egui::Color32::GREEN)));
-                        }
+                if ui.button("Thresholds...").clicked() {
+                    self.show_thresholds_window = true;
+                }
+                
+                if ui.button("Theme...").clicked() {
+                    self.show_theme_window = true;
+                }
+                
+                if ui.button("Shortcuts...").clicked() {
+                    self.show_shortcuts_window = true;
+                }
+                
+                if ui.button("Commands...").on_hover_text(&self.key_bindings.command_palette).clicked() {
+                    self.open_command_palette(ctx);
+                }
+                
+                if ui.button("Reset settings").on_hover_text("Restore the default interval, panels, sorting, color thresholds, theme and window size").clicked() {
+                    self.reset_settings(ctx);
+                }
+                
+                if ui.button("Export...").clicked() {
+                    self.show_export_window = true;
+                    self.export_status = None;
+                }
+                
+                if ui.button("Open snapshot...").clicked() {
+                    self.open_snapshot();
+                }
+                
+                if ui.button("Compare...").clicked() {
+                    self.compare.open = true;
+                }
+                
+                ui.separator();
+                
+                let mut exporter_enabled = self.metrics_server.is_some();
+                if ui.checkbox(&mut exporter_enabled, "Prometheus exporter").changed() {
+                    if exporter_enabled {
+                        self.start_metrics_server();
+                    } else {
+                        self.stop_metrics_server();
+                    }
+                }
+                match &self.metrics_server {
+                    Some(server) => {
+                        ui.label(egui::RichText::new(format!("http://{}/metrics", server.addr()))
+                            .color(color(self.palette.weak_text)));
                     }
+                    None => {
+                        ui.add(egui::TextEdit::singleline(&mut self.metrics_addr)
+                            .desired_width(120.0));
+                    }
+                }
+                if let Some(error) = &self.metrics_error {
+                    ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+                }
+                
+                let sinks_label = if self.sinks.is_empty() {
+                    "Sinks...".to_string()
+                } else {
+                    format!("Sinks ({})...", self.sinks.len())
+                };
+                if ui.button(sinks_label).clicked() {
+                    self.show_sinks_window = true;
+                    self.sinks_message = None;
+                }
+                
+                let log_label = if self.snapshot_log.is_some() { "Snapshot log (on)..." } else { "Snapshot log..." };
+                if ui.button(log_label).clicked() {
+                    self.show_snapshot_log_window = true;
+                }
+                
+                let active_alerts = self.alert_engine.active_count();
+                let alerts_label = if active_alerts > 0 {
+                    egui::RichText::new(format!("Alerts ({})...", active_alerts)).color(color(self.palette.critical))
+                } else {
+                    egui::RichText::new("Alerts...")
+                };
+                if ui.button(alerts_label).clicked() {
+                    self.show_alerts_window = true;
+                    self.alerts_message = None;
+                }
+                
+                if ui.button("Audit trail...").clicked() {
+                    self.show_audit_window = true;
+                }
+                
+                if let Some(error) = &self.snapshot_error {
+                    ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
                 }
             });
         });
@@ -379,6 +945,11 @@ impl eframe::App for ResourceMonitor {
                         ui.separator();
                     }
                     
+                    if self.show_users {
+                        self.render_users(ui);
+                        ui.separator();
+                    }
+                    
                     self.render_process_table(ui);
                 });
             
@@ -391,6 +962,18 @@ impl eframe::App for ResourceMonitor {
             }
         });
         
+        self.render_export_window(ctx);
+        self.render_compare_window(ctx);
+        self.render_sinks_window(ctx);
+        self.render_snapshot_log_window(ctx);
+        self.render_alerts_window(ctx);
+        self.render_audit_window(ctx);
+        self.render_thresholds_window(ctx);
+        self.render_theme_window(ctx, system_theme);
+        self.render_shortcuts_window(ctx);
+        self.render_kill_confirmation(ctx);
+        self.render_command_palette(ctx);
+        
         egui::SidePanel::right("details_panel")
             .min_width(250.0)
             .max_width(350.0)
@@ -398,6 +981,8 @@ impl eframe::App for ResourceMonitor {
             .show(ctx, |ui| {
                 self.render_process_details(ui);
             });
+        
+        self.persist_settings(ctx);
     }
 }
 
@@ -447,13 +1032,7 @@ impl ResourceMonitor {
                     ui.label(format!("Disk {} ({}, {})", 
                         disk.name, disk.disk_type, disk.file_system));
                     
-                    let usage_color = if disk.usage_percent > 90.0 {
-                        egui::Color32::RED
-                    } else if disk.usage_percent > 70.0 {
-                        egui::Color32::YELLOW
-                    } else {
-                        egui::Color32::GREEN
-                    };
+                    let usage_color = self.color_thresholds.disk_color(disk.usage_percent, &self.palette);
                     
                     ui.label(egui::RichText::new(format!("{:.1}%", disk.usage_percent))
                         .color(usage_color));
@@ -461,7 +1040,7 @@ impl ResourceMonitor {
                 
                 ui.add(egui::ProgressBar::new(disk.usage_percent / 100.0)
                     .desired_width(ui.available_width())
-                    .fill(egui::Color32::from_rgb(0, 120, 215)));
+                    .fill(color(self.palette.accent)));
                 
                 ui.horizontal(|ui| {
                     ui.label(format!("Used: {:.1} GB", 
@@ -477,144 +1056,202 @@ impl ResourceMonitor {
     }
 
     fn render_process_table(&mut self, ui: &mut egui::Ui) {
+    if let Some(snapshot) = &self.snapshot {
+        let mut close = false;
+        egui::Frame::none()
+            .fill(color(self.palette.banner))
+            .inner_margin(egui::Margin::symmetric(6.0, 4.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let taken_at = snapshot.taken_at
+                        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                        .unwrap_or_else(|| "unknown time".to_string());
+                    ui.label(egui::RichText::new(format!("OFFLINE SNAPSHOT: {} ({}, {} processes)",
+                        snapshot.file_name(), taken_at, snapshot.processes.len()))
+                        .strong()
+                        .color(color(self.palette.warning)));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Back to live data").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            });
+        if close {
+            self.close_snapshot();
+        }
+    }
+    
     ui.horizontal(|ui| {
-        ui.heading("Processes");
+        ui.heading(if self.snapshot.is_some() { "Processes (offline)" } else { "Processes" });
+        if self.process_indices.len() != self.processes.len() {
+            ui.label(egui::RichText::new(format!("{} of {} shown", self.process_indices.len(), self.processes.len()))
+                .color(ui.visuals().weak_text_color()));
+        }
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            ui.add(egui::TextEdit::singleline(&mut self.process_filter)
-                .hint_text("Search processes...")
-                .desired_width(150.0)
-                .interactive(true));
+            ui.menu_button("Filters", |ui| self.render_saved_filters(ui));
+            let search = ui.add(egui::TextEdit::singleline(&mut self.process_filter)
+                .id(egui::Id::new(shortcuts::SEARCH_FIELD_ID))
+                .hint_text("Search, e.g. user:root cpu>5")
+                .desired_width(250.0)
+                .interactive(true))
+                .on_hover_text(filter::QUERY_HELP);
+            if search.changed() {
+                self.apply_filter();
+            }
+            ui.menu_button("Columns", |ui| self.render_column_chooser(ui));
+            let mut group_by = self.group_by;
+            egui::ComboBox::from_id_source("group_by")
+                .selected_text(group_by.label())
+                .show_ui(ui, |ui| {
+                    for option in GroupBy::ALL {
+                        ui.selectable_value(&mut group_by, option, option.label());
+                    }
+                });
+            if group_by != self.group_by {
+                self.set_group_by(group_by);
+            }
         });
     });
+    if let Some(error) = self.filter_error() {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(egui::RichText::new(format!("⚠ {}", error)).color(ui.visuals().error_fg_color));
+        });
+    }
     
-    let column_widths = self.calculate_column_widths(ui.available_width());
+    // Header and rows scroll sideways together when the columns are wider than the panel
+    egui::ScrollArea::horizontal()
+        .id_source("process_table_columns")
+        .auto_shrink([false; 2])
+        .show(ui, |ui| {
+            self.render_table_header(ui);
+            ui.add_space(2.0);
+            ui.separator();
+            ui.add_space(2.0);
+            self.render_table_rows(ui);
+        });
+}
+
+    fn render_table_rows(&mut self, ui: &mut egui::Ui) {
+    let scroll_height = ui.available_height();
+    let row_step = self.row_height + 4.0 + ui.spacing().item_spacing.y;
+    self.table_page_rows = (scroll_height / row_step).floor().max(1.0) as usize;
     
-    ui.horizontal(|ui| {
-        let name_label = egui::RichText::new("Process Name").color(egui::Color32::from_gray(220));
-        let name_response = ui.add_sized([column_widths.name, self.row_height], 
-            egui::SelectableLabel::new(self.sort_column == SortColumn::Name, name_label)
-        );
-        if name_response.clicked() {
-            if self.sort_column == SortColumn::Name {
-                self.sort_descending = !self.sort_descending;
-            } else {
-                self.sort_column = SortColumn::Name;
-                self.sort_descending = false;
-            }
-            self.sort_process_indices();
-        }
-        
-        let cpu_label = egui::RichText::new("CPU %").color(egui::Color32::from_gray(220));
-        let cpu_response = ui.add_sized([column_widths.cpu, self.row_height],
-            egui::SelectableLabel::new(self.sort_column == SortColumn::Cpu, cpu_label)
-        );
-        if cpu_response.clicked() {
-            if self.sort_column == SortColumn::Cpu {
-                self.sort_descending = !self.sort_descending;
-            } else {
-                self.sort_column = SortColumn::Cpu;
-                self.sort_descending = true;
-            }
-            self.sort_process_indices();
-        }
-        
-        let memory_label = egui::RichText::new("Memory (MB)").color(egui::Color32::from_gray(220));
-        let memory_response = ui.add_sized([column_widths.memory, self.row_height],
-            egui::SelectableLabel::new(self.sort_column == SortColumn::Memory, memory_label)
-        );
-        if memory_response.clicked() {
-            if self.sort_column == SortColumn::Memory {
-                self.sort_descending = !self.sort_descending;
+    let mut scroll_area = egui::ScrollArea::vertical()
+        .id_source("process_table_scroll")
+        .max_height(scroll_height)
+        .auto_shrink([false; 2]);
+    
+    // Keyboard navigation keeps the selected row in view; it may not be laid out, so scroll by offset
+    if std::mem::take(&mut self.scroll_to_selected) {
+        let position = self.selected.and_then(|key| self.row_of(key));
+        if let Some(position) = position {
+            let row_top = position as f32 * row_step;
+            let offset = if row_top < self.table_scroll_offset {
+                row_top
+            } else if row_top + row_step > self.table_scroll_offset + scroll_height {
+                row_top + row_step - scroll_height
             } else {
-                self.sort_column = SortColumn::Memory;
-                self.sort_descending = true;
-            }
-            self.sort_process_indices();
+                self.table_scroll_offset
+            };
+            scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
         }
-        
-        let status_label = egui::RichText::new("Status").color(egui::Color32::from_gray(220));
-        let status_response = ui.add_sized([column_widths.status, self.row_height],
-            egui::SelectableLabel::new(self.sort_column == SortColumn::Status, status_label)
-        );
-        if status_response.clicked() {
-            if self.sort_column == SortColumn::Status {
-                self.sort_descending = !self.sort_descending;
-            } else {
-                self.sort_column = SortColumn::Status;
-                self.sort_descending = false;
-            }
-            self.sort_process_indices();
+    } else if let Some((key, position, offset)) = self.table_anchor {
+        // Rows shift as processes start, exit or change order; keep the anchored one still on screen
+        if let Some(new_position) = self.row_of(key).filter(|&p| p != position) {
+            let shift = (new_position as f32 - position as f32) * row_step;
+            scroll_area = scroll_area.vertical_scroll_offset((offset + shift).max(0.0));
         }
-        
-        let user_label = egui::RichText::new("User").color(egui::Color32::from_gray(220));
-        ui.add_sized([column_widths.user, self.row_height], 
-            egui::Label::new(user_label));
-    });
-    
-    ui.add_space(2.0);
-    ui.separator();
-    ui.add_space(2.0);
+    }
     
-    let scroll_height = ui.available_height();
+    let mut new_hovered = None;
+    let mut select = None;
     
-    egui::ScrollArea::vertical()
-        .max_height(scroll_height)
-        .auto_shrink([false; 2])
-        .show(ui, |ui| {
-            let indices = self.process_indices.clone();
-            let mut new_hovered_row = None;
-            let mut select_pid = None;
-            
-            for &index in &indices {
-                let process = &self.processes[index];
-                let (is_hovered, is_clicked) = self.render_table_row(
-                    ui, 
-                    process, 
-                    &column_widths, 
-                    index,
-                    self.row_height
-                );
-                
-                if is_hovered {
-                    new_hovered_row = Some(index);
-                }
-                
-                if is_clicked {
-                    select_pid = Some(process.pid);
+    // Only the rows in view are painted
+    let mut toggle = None;
+    let output = scroll_area.show_rows(ui, self.row_height + 4.0, self.table_rows.len(), |ui, row_range| {
+        for position in row_range {
+            let index = match self.table_rows[position] {
+                TableRow::Group(group) => {
+                    if self.render_group_row(ui, &self.groups[group], position).1 {
+                        toggle = Some(group);
+                    }
+                    continue;
                 }
-            }
+                TableRow::Process(index) | TableRow::Member(index) => index,
+            };
+            let process = &self.processes[index];
+            let indent = if matches!(self.table_rows[position], TableRow::Member(_)) { grouping::MEMBER_INDENT } else { 0.0 };
+            let (is_hovered, is_clicked) = self.render_table_row(
+                ui, 
+                process, 
+                position,
+                self.row_height,
+                indent,
+            );
             
-            // Обработка выбора процесса после отрисовки
-            if let Some(pid) = select_pid {
-                self.selected_pid = Some(pid);
+            if is_hovered {
+                new_hovered = Some(process.key());
             }
             
-            self.hovered_row = new_hovered_row;
-        });
+            if is_clicked {
+                select = Some(process.key());
+            }
+        }
+    });
+    
+    // Обработка выбора процесса после отрисовки
+    if let Some(key) = select {
+        self.selected = Some(key);
+    }
+    if let Some(group) = toggle {
+        self.toggle_group(group);
+    }
+    
+    self.hovered = new_hovered;
+    self.table_scroll_offset = output.state.offset.y;
+    self.table_anchor = self.scroll_anchor(row_step);
 }
 
+    // The selected row when it is in view, else the top row; none at the very top so new rows show up there
+    fn scroll_anchor(&self, row_step: f32) -> Option<(ProcessKey, usize, f32)> {
+        let offset = self.table_scroll_offset;
+        if offset <= 0.0 || self.table_rows.is_empty() {
+            return None;
+        }
+        let first = ((offset / row_step) as usize).min(self.table_rows.len() - 1);
+        let in_view = first..first + self.table_page_rows;
+        // Group rows have no process to follow, so take the first process row in view
+        let position = self.selected
+            .and_then(|key| self.row_of(key))
+            .filter(|position| in_view.contains(position))
+            .or_else(|| in_view.clone().find(|&p| self.table_rows.get(p).is_some_and(|row| row.process().is_some())))?;
+        let index = self.table_rows[position].process()?;
+        Some((self.processes[index].key(), position, offset))
+    }
+
     fn render_table_row(
     &self,
     ui: &mut egui::Ui,
     process: &ProcessInfo,
-    column_widths: &ColumnWidths,
     row_index: usize,
     row_height: f32,
+    indent: f32,
 ) -> (bool, bool) {
     let bg_color = if row_index % 2 == 0 {
-        egui::Color32::from_rgba_unmultiplied(30, 30, 30, 255)
+        color(self.palette.row_even)
     } else {
-        egui::Color32::from_rgba_unmultiplied(40, 40, 40, 255)
+        color(self.palette.row_odd)
     };
 
     let (response, painter) = ui.allocate_painter(
-        egui::vec2(ui.available_width(), row_height + 4.0),
+        egui::vec2(ui.available_width().max(self.table_width()), row_height + 4.0),
         egui::Sense::click(),
     );
     let rect = response.rect;
     let is_hovered = response.hovered();
     let is_clicked = response.clicked();
-    let is_selected = self.selected_pid.map(|pid| pid == process.pid).unwrap_or(false);
+    let is_selected = self.selected == Some(process.key());
 
     // Фон
     painter.rect_filled(rect, 0.0, bg_color);
@@ -624,7 +1261,7 @@ impl ResourceMonitor {
         painter.rect_stroke(
             rect,
             0.0,
-            egui::Stroke::new(1.0, egui::Color32::from_gray(100)),
+            egui::Stroke::new(1.0, color(self.palette.hover)),
         );
     }
 
@@ -633,202 +1270,299 @@ impl ResourceMonitor {
         painter.rect_stroke(
             rect,
             0.0,
-            egui::Stroke::new(2.0, egui::Color32::from_rgb(0, 120, 215)),
+            egui::Stroke::new(2.0, color(self.palette.accent)),
         );
     }
 
     // Параметры текста
     let font_id = egui::TextStyle::Body.resolve(ui.style());
-    let text_color = egui::Color32::from_gray(220);
-    let x_offset = rect.min.x + 4.0;
-    let y_center = rect.center().y;
-
-    // Имя
-    painter.text(
-        egui::pos2(x_offset, y_center),
-        egui::Align2::LEFT_CENTER,
-        &process.name,
-        font_id.clone(),
-        text_color,
-    );
-
-    // CPU
-    let cpu_x = x_offset + column_widths.name;
-    let cpu_color = if process.cpu_usage > 50.0 {
-        egui::Color32::from_rgb(255, 100, 100)
-    } else if process.cpu_usage > 20.0 {
-        egui::Color32::from_rgb(255, 200, 100)
-    } else {
-        text_color
-    };
-    painter.text(
-        egui::pos2(cpu_x, y_center),
-        egui::Align2::LEFT_CENTER,
-        &format!("{:.1}%", process.cpu_usage),
-        font_id.clone(),
-        cpu_color,
-    );
-
-    // Memory
-    let memory_x = cpu_x + column_widths.cpu;
-    let memory_mb = process.memory_usage as f64 / 1024.0 / 1024.0;
-    let memory_color = if memory_mb > 500.0 {
-        egui::Color32::from_rgb(255, 100, 100)
-    } else if memory_mb > 100.0 {
-        egui::Color32::from_rgb(255, 200, 100)
-    } else {
-        text_color
-    };
-    painter.text(
-        egui::pos2(memory_x, y_center),
-        egui::Align2::LEFT_CENTER,
-        &format!("{:.1} MB", memory_mb),
-        font_id.clone(),
-        memory_color,
-    );
-
-    // Status
-    let status_x = memory_x + column_widths.memory;
-    let status_color = if process.status.contains("Run") {
-        egui::Color32::from_rgb(100, 200, 100)
-    } else {
-        egui::Color32::from_gray(150)
-    };
-    painter.text(
-        egui::pos2(status_x, y_center),
-        egui::Align2::LEFT_CENTER,
-        &process.status,
-        font_id.clone(),
-        status_color,
-    );
-
-    // User
-    let user_x = status_x + column_widths.status;
-    painter.text(
-        egui::pos2(user_x, y_center),
-        egui::Align2::LEFT_CENTER,
-        process.user.as_deref().unwrap_or("N/A"),
-        font_id,
-        text_color,
-    );
+    self.paint_row_cells(&painter, rect, process, &font_id, indent);
 
     (is_hovered, is_clicked)
 }
 
-    fn render_charts(&self, ui: &mut egui::Ui) {
-        ui.heading("Resource Usage Charts");
+    fn render_charts(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Resource Usage Charts");
+            ui.separator();
+            
+            ui.label("Range: ");
+            for range in ChartRange::ALL {
+                if ui.selectable_value(&mut self.chart_range, range, range.label()).clicked() {
+                    self.chart_reset = true;
+                }
+            }
+            if self.chart_range == ChartRange::Custom {
+                let minutes = egui::DragValue::new(&mut self.chart_custom_minutes)
+                    .clamp_range(1.0..=1440.0)
+                    .suffix(" min");
+                if ui.add(minutes).changed() {
+                    self.chart_reset = true;
+                }
+            }
+            
+            if ui.button("Reset view").clicked() {
+                self.chart_reset = true;
+            }
+            
+            ui.separator();
+            
+            ui.label("Top processes kept: ");
+            ui.add(egui::DragValue::new(&mut self.top_processes_per_sample).clamp_range(1..=20))
+                .on_hover_text("Processes by CPU and by memory stored with every sample");
+            
+            ui.separator();
+            
+            // Crosshair readout for the hovered moment
+            match self.chart_hover_time.and_then(|t| self.sample_near(t)) {
+                Some(sample) => {
+                    ui.label(egui::RichText::new(format!("{}  CPU: {:.1}%  Memory: {:.1} MB",
+                        format_timestamp(sample.timestamp, "%H:%M:%S"),
+                        sample.cpu_usage,
+                        sample.memory_mb))
+                        .color(color(self.palette.text)));
+                }
+                None => {
+                    ui.label(egui::RichText::new("Drag to pan, scroll to zoom, click to inspect a sample")
+                        .color(color(self.palette.weak_text)));
+                }
+            }
+        });
+        
+        let now = unix_timestamp();
+        let start = now - self.chart_range.seconds(self.chart_custom_minutes);
+        let visible_from = self.history.partition_point(|s| s.timestamp < start);
+        let cpu_points = downsample(
+            self.history.range(visible_from..).map(|s| [s.timestamp, s.cpu_usage]).collect(),
+            MAX_CHART_POINTS,
+        );
+        let memory_points = downsample(
+            self.history.range(visible_from..).map(|s| [s.timestamp, s.memory_mb]).collect(),
+            MAX_CHART_POINTS,
+        );
+        
+        let reset = std::mem::take(&mut self.chart_reset);
+        let selected_time = self.selected_sample_time;
+        let plot_height = (ui.available_height() - 10.0).max(120.0);
+        let mut hover_time = None;
+        let mut clicked_time = None;
         
         ui.horizontal(|ui| {
-            let cpu_plot = Plot::new("cpu_plot")
-                .height(180.0)
-                .width(ui.available_width() * 0.49)
-                .legend(Legend::default())
+            let plot_fraction = if selected_time.is_some() { 0.37 } else { 0.49 };
+            let plot_width = ui.available_width() * plot_fraction;
+            
+            let cpu_plot = history_plot("cpu_plot", plot_width, plot_height, start, now, reset)
+                .include_y(0.0)
+                .include_y(100.0)
                 .label_formatter(|name, value| {
+                    let time = format_timestamp(value.x, "%H:%M:%S");
                     if name.is_empty() {
-                        format!("CPU: {:.1}%", value.y)
+                        format!("{time}\nCPU: {:.1}%", value.y)
                     } else {
-                        format!("{name}: {:.1}%", value.y)
+                        format!("{time}\n{name}: {:.1}%", value.y)
                     }
                 });
             
-            cpu_plot.show(ui, |plot_ui| {
-                if !self.history.is_empty() {
-                    let points: Vec<[f64; 2]> = self.history
-                        .iter()
-                        .enumerate()
-                        .map(|(i, &(cpu, _))| [i as f64, cpu])
-                        .collect();
-                    
-                    let line = Line::new(points)
+            let cpu_response = cpu_plot.show(ui, |plot_ui| {
+                if !cpu_points.is_empty() {
+                    let line = Line::new(cpu_points)
                         .name("CPU %")
-                        .color(egui::Color32::from_rgb(255, 100, 100));
+                        .color(color(self.palette.chart_cpu));
                     plot_ui.line(line);
                 }
+                if let Some(time) = selected_time {
+                    plot_ui.vline(VLine::new(time).color(color(self.palette.accent)));
+                }
+                plot_pointer_time(plot_ui)
             });
             
-            let memory_plot = Plot::new("memory_plot")
-                .height(180.0)
-                .width(ui.available_width() * 0.49)
-                .legend(Legend::default())
+            let memory_plot = history_plot("memory_plot", plot_width, plot_height, start, now, reset)
+                .include_y(0.0)
                 .label_formatter(|name, value| {
+                    let time = format_timestamp(value.x, "%H:%M:%S");
                     if name.is_empty() {
-                        format!("Memory: {:.1} MB", value.y)
+                        format!("{time}\nMemory: {:.1} MB", value.y)
                     } else {
-                        format!("{name}: {:.1} MB", value.y)
+                        format!("{time}\n{name}: {:.1} MB", value.y)
                     }
                 });
             
-            memory_plot.show(ui, |plot_ui| {
-                if !self.history.is_empty() {
-                    let points: Vec<[f64; 2]> = self.history
-                        .iter()
-                        .enumerate()
-                        .map(|(i, &(_, memory))| [i as f64, memory])
-                        .collect();
-                    
-                    let line = Line::new(points)
+            let memory_response = memory_plot.show(ui, |plot_ui| {
+                if !memory_points.is_empty() {
+                    let line = Line::new(memory_points)
                         .name("Memory MB")
-                        .color(egui::Color32::from_rgb(100, 150, 255));
+                        .color(color(self.palette.chart_memory));
                     plot_ui.line(line);
                 }
+                if let Some(time) = selected_time {
+                    plot_ui.vline(VLine::new(time).color(color(self.palette.accent)));
+                }
+                plot_pointer_time(plot_ui)
             });
+            
+            for plot_response in [cpu_response, memory_response] {
+                let (hovered, clicked) = plot_response.inner;
+                
+                // Show who was busy at the hovered moment
+                if let Some(sample) = hovered.and_then(|t| self.sample_near(t)) {
+                    plot_response.response.on_hover_ui(|ui| {
+                        ui.label(egui::RichText::new(format_timestamp(sample.timestamp, "%H:%M:%S"))
+                            .strong());
+                        self.render_top_processes(ui, sample, false);
+                    });
+                }
+                
+                hover_time = hover_time.or(hovered);
+                clicked_time = clicked_time.or(clicked);
+            }
+            
+            if selected_time.is_some() {
+                ui.separator();
+                ui.vertical(|ui| {
+                    self.render_sample_details(ui);
+                });
+            }
         });
+        
+        self.chart_hover_time = hover_time;
+        if let Some(time) = clicked_time {
+            self.selected_sample_time = self.sample_near(time).map(|s| s.timestamp);
+        }
+    }
+
+    fn render_sample_details(&mut self, ui: &mut egui::Ui) {
+        let Some(sample) = self.selected_sample_time.and_then(|t| self.sample_near(t)).cloned() else {
+            self.selected_sample_time = None;
+            return;
+        };
+        
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(format!("Sample at {}",
+                format_timestamp(sample.timestamp, "%Y-%m-%d %H:%M:%S")))
+                .strong()
+                .color(color(self.palette.text)));
+            if ui.small_button("✕").clicked() {
+                self.selected_sample_time = None;
+            }
+        });
+        ui.label(egui::RichText::new(format!("CPU: {:.1}%  Memory: {:.1} MB",
+            sample.cpu_usage, sample.memory_mb))
+            .color(color(self.palette.text)));
+        ui.add_space(4.0);
+        
+        if let Some(pid) = self.render_top_processes(ui, &sample, true) {
+            self.selected = self.processes.iter().find(|p| p.pid == pid).map(ProcessInfo::key);
+            self.scroll_to_selected = true;
+        }
+    }
+
+    // Lists the processes stored with a sample; returns a clicked PID that is still running
+    fn render_top_processes(&self, ui: &mut egui::Ui, sample: &HistorySample, interactive: bool) -> Option<u32> {
+        let mut clicked_pid = None;
+        
+        let lists = [
+            ("Top processes by CPU: ", &sample.top_cpu),
+            ("Top processes by memory: ", &sample.top_memory),
+        ];
+        for (index, (title, processes)) in lists.into_iter().enumerate() {
+            ui.label(egui::RichText::new(title).color(color(self.palette.text)));
+            egui::Grid::new(("sample_top_grid", index, interactive))
+                .num_columns(3)
+                .striped(true)
+                .spacing([10.0, 2.0])
+                .show(ui, |ui| {
+                    for process in processes {
+                        let alive = self.processes.iter().any(|p| p.pid == process.pid);
+                        // Processes that have exited since are dimmed
+                        let name = egui::RichText::new(&process.name).color(if alive {
+                            ui.visuals().strong_text_color()
+                        } else {
+                            color(self.palette.weak_text)
+                        });
+                        if interactive {
+                            let response = ui.add(egui::Label::new(name).sense(egui::Sense::click()))
+                                .on_hover_text(format!("PID: {}", process.pid));
+                            if response.clicked() && alive {
+                                clicked_pid = Some(process.pid);
+                            }
+                        } else {
+                            ui.label(name);
+                        }
+                        ui.label(format!("{:.1}%", process.cpu_usage));
+                        ui.label(format!("{:.1} MB", process.memory_usage as f64 / 1024.0 / 1024.0));
+                        ui.end_row();
+                    }
+                });
+            ui.add_space(4.0);
+        }
+        
+        clicked_pid
     }
 
     fn render_process_details(&mut self, ui: &mut egui::Ui) {
     ui.heading("Process Details");
     
-    if let Some(pid) = self.selected_pid {
+    if let Some(key) = self.selected {
         // Находим индекс процесса и извлекаем все необходимые данные
-        if let Some(index) = self.process_indices.iter().position(|&i| self.processes[i].pid == pid) {
+        if let Some(index) = self.process_indices.iter().copied().find(|&i| self.processes[i].key() == key) {
             // Копируем данные, чтобы не держать ссылку на self.processes
-            let (name, pid, cpu_usage, memory_usage, status, user, command_line) = {
-                let p = &self.processes[self.process_indices[index]];
+            let (name, pid, cpu_usage, memory_usage, status, command_line, start_time) = {
+                let p = &self.processes[index];
                 (
                     p.name.clone(),
                     p.pid,
                     p.cpu_usage,
                     p.memory_usage,
                     p.status.clone(),
-                    p.user.clone(),
                     p.command_line.clone(),
+                    p.start_time,
                 )
             };
+            let identity = identity_rows(&self.processes[index]);
             
             egui::Grid::new("details_grid")
                 .num_columns(2)
                 .spacing([10.0, 5.0])
                 .show(ui, |ui| {
-                    ui.label(egui::RichText::new("Name: ").color(egui::Color32::from_gray(220)));
-                    ui.label(egui::RichText::new(&name).color(egui::Color32::WHITE));
+                    ui.label(egui::RichText::new("Name: ").color(color(self.palette.text)));
+                    ui.label(egui::RichText::new(&name).color(ui.visuals().strong_text_color()));
                     ui.end_row();
                     
-                    ui.label(egui::RichText::new("PID: ").color(egui::Color32::from_gray(220)));
-                    ui.label(egui::RichText::new(pid.to_string()).color(egui::Color32::WHITE));
+                    ui.label(egui::RichText::new("PID: ").color(color(self.palette.text)));
+                    ui.label(egui::RichText::new(pid.to_string()).color(ui.visuals().strong_text_color()));
                     ui.end_row();
+                    
+                    if start_time > 0 {
+                        ui.label(egui::RichText::new("Started: ").color(color(self.palette.text)));
+                        ui.label(egui::RichText::new(format_timestamp(start_time as f64, "%Y-%m-%d %H:%M:%S"))
+                            .color(ui.visuals().strong_text_color()));
+                        ui.end_row();
+                    }
                      
-                    ui.label(egui::RichText::new("CPU: ").color(egui::Color32::from_gray(220)));
-                    ui.label(egui::RichText::new(format!("{:.1}% ", cpu_usage)).color(egui::Color32::WHITE));
+                    ui.label(egui::RichText::new("CPU: ").color(color(self.palette.text)));
+                    ui.label(egui::RichText::new(format!("{:.1}% ", cpu_usage)).color(ui.visuals().strong_text_color()));
                     ui.end_row();
                     
-                    ui.label(egui::RichText::new("Memory: ").color(egui::Color32::from_gray(220)));
+                    ui.label(egui::RichText::new("Memory: ").color(color(self.palette.text)));
                     ui.label(egui::RichText::new(format!("{:.1} MB ", 
                         memory_usage as f64 / 1024.0 / 1024.0))
-                        .color(egui::Color32::WHITE));
+                        .color(ui.visuals().strong_text_color()));
                     ui.end_row();
                      
-                    ui.label(egui::RichText::new("Status: ").color(egui::Color32::from_gray(220)));
-                    ui.label(egui::RichText::new(&status).color(egui::Color32::WHITE));
+                    ui.label(egui::RichText::new("Status: ").color(color(self.palette.text)));
+                    ui.label(egui::RichText::new(&status).color(ui.visuals().strong_text_color()));
                     ui.end_row();
                     
-                    ui.label(egui::RichText::new("User: ").color(egui::Color32::from_gray(220)));
-                    ui.label(egui::RichText::new(user.as_deref().unwrap_or("N/A")).color(egui::Color32::WHITE));
-                    ui.end_row();
+                    for (label, value) in &identity {
+                        ui.label(egui::RichText::new(*label).color(color(self.palette.text)));
+                        ui.label(egui::RichText::new(value).color(ui.visuals().strong_text_color()));
+                        ui.end_row();
+                    }
                     
                     if let Some(cmd) = &command_line {
-                        ui.label(egui::RichText::new("Command: ").color(egui::Color32::from_gray(220)));
+                        ui.label(egui::RichText::new("Command: ").color(color(self.palette.text)));
                         egui::ScrollArea::horizontal().show(ui, |ui| {
-                            ui.label(egui::RichText::new(cmd).color(egui::Color32::WHITE));
+                            ui.label(egui::RichText::new(cmd).color(ui.visuals().strong_text_color()));
                         });
                         ui.end_row();
                     }
@@ -837,7 +1571,11 @@ impl ResourceMonitor {
             ui.separator();
             
             ui.horizontal(|ui| {
-                if ui.button("Terminate Process").clicked() {
+                let is_live = self.snapshot.is_none();
+                if ui.add_enabled(is_live, egui::Button::new("Terminate Process"))
+                    .on_disabled_hover_text("Snapshot data is read-only")
+                    .clicked()
+                {
                     self.kill_selected_process();
                 }
             });
@@ -845,49 +1583,260 @@ impl ResourceMonitor {
             ui.separator();
             
             ui.vertical(|ui| {
-                ui.label(egui::RichText::new("CPU Usage: ").color(egui::Color32::from_gray(220)));
+                ui.label(egui::RichText::new("CPU Usage: ").color(color(self.palette.text)));
                 ui.add(egui::ProgressBar::new(cpu_usage / 100.0)
                     .text(format!("{:.1}% ", cpu_usage))
                     .desired_width(ui.available_width())
-                    .fill(egui::Color32::from_rgb(0, 120, 215)));
+                    .fill(color(self.palette.accent)));
                 
-                ui.label(egui::RichText::new("Memory Usage: ").color(egui::Color32::from_gray(220)));
-                let memory_percent = (memory_usage as f64 / self.system.total_memory() as f64) * 100.0;
-                ui.add(egui::ProgressBar::new(memory_percent as f32 / 100.0)
-                    .text(format!("{:.1}% of total memory ", memory_percent))
-                    .desired_width(ui.available_width())
-                    .fill(egui::Color32::from_rgb(0, 120, 215)));
+                // The snapshot may come from another machine, so its share of local memory means nothing
+                if self.snapshot.is_none() {
+                    ui.label(egui::RichText::new("Memory Usage: ").color(color(self.palette.text)));
+                    let memory_percent = (memory_usage as f64 / self.system.total_memory() as f64) * 100.0;
+                    ui.add(egui::ProgressBar::new(memory_percent as f32 / 100.0)
+                        .text(format!("{:.1}% of total memory ", memory_percent))
+                        .desired_width(ui.available_width())
+                        .fill(color(self.palette.accent)));
+                }
             });
         }
     } else {
         ui.vertical_centered(|ui| {
             ui.label(egui::RichText::new("Select a process from the table ")
-                .color(egui::Color32::from_gray(220)));
+                .color(color(self.palette.text)));
             ui.label(egui::RichText::new("to view details ")
-                .color(egui::Color32::from_gray(220)));
+                .color(color(self.palette.text)));
             ui.add_space(20.0);
-            ui.colored_label(egui::Color32::from_gray(150), "← Click on a row ");
+            ui.colored_label(color(self.palette.weak_text), "← Click on a row ");
         });
     }
 }
 }
 
+// `interval_secs` is the time since the previous refresh, for the disk rates
+fn process_info(pid: Pid, process: &sysinfo::Process, interval_secs: f32, names: &UserNames) -> ProcessInfo {
+    let name = if process.name().is_empty() {
+        format!("PID: {}", pid.as_u32())
+    } else {
+        process.name().to_string()
+    };
+    
+    let command_line = if process.cmd().is_empty() {
+        None
+    } else {
+        Some(process.cmd().join(" "))
+    };
+    let disk_usage = process.disk_usage();
+    let interval_secs = interval_secs.max(0.001) as f64;
+    
+    ProcessInfo {
+        name,
+        pid: pid.as_u32(),
+        cpu_usage: process.cpu_usage(),
+        memory_usage: process.memory(),
+        status: format!("{:?}", process.status()),
+        user: names.user(process.user_id()),
+        effective_user: names.user(process.effective_user_id()),
+        group: names.group(process.group_id()),
+        effective_group: names.group(process.effective_group_id()),
+        command_line,
+        start_time: process.start_time(),
+        parent_pid: process.parent().map(|pid| pid.as_u32()),
+        // The task list leaves out the main thread
+        threads: process.tasks().map(|tasks| tasks.len() as u32 + 1),
+        virtual_memory: process.virtual_memory(),
+        nice: actions::process_nice(pid.as_u32()),
+        run_time: process.run_time(),
+        disk_read_per_sec: disk_usage.read_bytes as f64 / interval_secs,
+        disk_write_per_sec: disk_usage.written_bytes as f64 / interval_secs,
+        exe: process.exe().map(|path| path.display().to_string()),
+    }
+}
+
+// User and group lines of the details panel; the effective IDs are listed only when they
+// differ from the real ones, as for setuid programs
+fn identity_rows(process: &ProcessInfo) -> Vec<(&'static str, String)> {
+    let mut rows = vec![("User: ", process.user.clone().unwrap_or_else(|| "N/A".to_string()))];
+    if let Some(effective) = process.effective_user.as_ref().filter(|&e| Some(e) != process.user.as_ref()) {
+        rows.push(("Effective user: ", effective.clone()));
+    }
+    if let Some(group) = &process.group {
+        rows.push(("Group: ", group.clone()));
+    }
+    if let Some(effective) = process.effective_group.as_ref().filter(|&e| Some(e) != process.group.as_ref()) {
+        rows.push(("Effective group: ", effective.clone()));
+    }
+    rows
+}
+
+// Current wall-clock time in seconds since the Unix epoch
+fn unix_timestamp() -> f64 {
+    Local::now().timestamp_millis() as f64 / 1000.0
+}
+
+fn format_timestamp(timestamp: f64, format: &str) -> String {
+    match Local.timestamp_millis_opt((timestamp * 1000.0) as i64).single() {
+        Some(time) => time.format(format).to_string(),
+        None => String::new(),
+    }
+}
+
+// Reduces a series to at most `max_points`, keeping the peak of every bucket
+fn downsample(points: Vec<[f64; 2]>, max_points: usize) -> Vec<[f64; 2]> {
+    if points.len() <= max_points || max_points == 0 {
+        return points;
+    }
+    let bucket = points.len().div_ceil(max_points);
+    points
+        .chunks(bucket)
+        .filter_map(|chunk| chunk.iter().copied().max_by(|a, b| a[1].total_cmp(&b[1])))
+        .collect()
+}
+
+// Common setup for the history charts: shared X axis, cursor and time labels
+fn history_plot(id: &str, width: f32, height: f32, start: f64, end: f64, reset: bool) -> Plot {
+    let plot = Plot::new(id)
+        .height(height)
+        .width(width)
+        .legend(Legend::default())
+        .link_axis("history_charts", true, false)
+        .link_cursor("history_charts", true, false)
+        .allow_zoom([true, false])
+        .allow_drag([true, false])
+        .include_x(start)
+        .include_x(end)
+        .x_axis_formatter(|mark, _, _| format_timestamp(mark.value, "%H:%M:%S"));
+    if reset { plot.reset() } else { plot }
+}
+
+// Reports the hovered and clicked plot time, if any
+fn plot_pointer_time(plot_ui: &egui_plot::PlotUi) -> (Option<f64>, Option<f64>) {
+    let pointer = plot_ui.pointer_coordinate().map(|p| p.x);
+    let hovered = if plot_ui.response().hovered() { pointer } else { None };
+    let clicked = if plot_ui.response().clicked() { pointer } else { None };
+    (hovered, clicked)
+}
+
+// Command-line switches
+struct CliArgs {
+    headless: bool,
+    print_config: bool,
+    config_path: Option<std::path::PathBuf>,
+    // Config keys set on the command line, applied over every other source
+    overrides: Vec<(String, toml::Value)>,
+}
+
+impl CliArgs {
+    fn parse() -> Self {
+        let mut cli = CliArgs {
+            headless: false,
+            print_config: false,
+            config_path: None,
+            overrides: Vec::new(),
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => cli.headless = true,
+                "--print-config" => cli.print_config = true,
+                "--config" => cli.config_path = args.next().map(Into::into),
+                "--interval" => {
+                    if let Some(value) = args.next() {
+                        cli.overrides.push(("interval".to_string(), config::parse_value(&value)));
+                    }
+                }
+                "--metrics-addr" => {
+                    if let Some(addr) = args.next() {
+                        cli.overrides.push(("metrics.enabled".to_string(), toml::Value::Boolean(true)));
+                        cli.overrides.push(("metrics.addr".to_string(), toml::Value::String(addr)));
+                    }
+                }
+                "--set" => match args.next().as_deref().and_then(|pair| pair.split_once('=')) {
+                    Some((key, value)) => cli.overrides.push((key.trim().to_string(), config::parse_value(value))),
+                    None => eprintln!("--set expects key=value"),
+                },
+                other => match other.strip_prefix("--").and_then(|flag| flag.split_once('=')) {
+                    // Any config key works as a flag: --retention-hours=48, --panels.charts=false
+                    Some((key, value)) => cli.overrides.push((key.replace('-', "_"), config::parse_value(value))),
+                    None => eprintln!("Unknown argument: {}", other),
+                },
+            }
+        }
+        cli
+    }
+}
+
+// Collects and exports without opening a window
+fn run_headless(mut monitor: ResourceMonitor) {
+    if monitor.metrics_server.is_none() && monitor.sinks.is_empty() && monitor.snapshot_log.is_none()
+        && monitor.notifiers.is_empty()
+    {
+        eprintln!("Headless mode has nothing to do: no exporter, sink, snapshot log or notification channel is running");
+        return;
+    }
+    loop {
+        monitor.refresh();
+        std::thread::sleep(std::time::Duration::from_secs_f32(monitor.update_interval));
+    }
+}
+
+// Without a console of its own, --print-config, --headless and config errors would print nothing,
+// so they write to the console of the shell that started SysWatch, if there is one
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Fails harmlessly when started from Explorer or with output already redirected
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
 fn main() -> Result<(), eframe::Error> {
+    if std::env::args_os().len() > 1 {
+        attach_parent_console();
+    }
+    let cli = CliArgs::parse();
+    
+    let loaded = config::load(cli.config_path.as_deref(), &cli.overrides);
+    if cli.print_config {
+        config::print(&loaded);
+        std::process::exit(if loaded.errors.is_empty() { 0 } else { 1 });
+    }
+    for error in &loaded.errors {
+        eprintln!("Config: {}", error);
+    }
+    
+    let settings = Settings::load();
+    let mut monitor = ResourceMonitor::new();
+    monitor.apply_config(&loaded.config);
+    monitor.saved_settings = settings.clone();
+    monitor.load_audit_log();
+    
+    if cli.headless {
+        run_headless(monitor);
+        return Ok(());
+    }
+    
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1400.0, 900.0])
+        viewport: settings.viewport(egui::ViewportBuilder::default())
             .with_resizable(true)
             .with_icon(load_icon()), // <-- Устанавливаем иконку
+        // Keeps the OS theme reported in frame.info() for the "Follow system" theme
+        follow_system_theme: true,
         ..Default::default()
     };
     
     eframe::run_native(
         "SysWatch",
         options,
-        Box::new(|cc| {
-            cc.egui_ctx.set_visuals(egui::Visuals::dark());
-            Box::new(ResourceMonitor::new())
-        }),
+        Box::new(|_cc| Box::new(monitor)),
     )
 }
 
@@ -914,4 +1863,85 @@ fn load_icon() -> egui::IconData {
             egui::IconData::default()
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: f64) -> HistorySample {
+        HistorySample { timestamp, cpu_usage: 0.0, memory_mb: 0.0, top_cpu: Vec::new(), top_memory: Vec::new() }
+    }
+
+    fn timestamps(monitor: &ResourceMonitor) -> Vec<f64> {
+        monitor.history.iter().map(|s| s.timestamp).collect()
+    }
+
+    #[test]
+    fn history_keeps_every_sample_within_the_budget() {
+        let mut monitor = ResourceMonitor::new();
+        monitor.update_interval = 1.0;
+        monitor.history_retention_secs = 10.0;
+        monitor.max_history_points = 20;
+        for t in 0..30 {
+            monitor.push_history_sample(sample(t as f64));
+        }
+        assert_eq!(timestamps(&monitor), (19..30).map(f64::from).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn history_thins_older_samples_past_the_budget() {
+        let mut monitor = ResourceMonitor::new();
+        monitor.update_interval = 1.0;
+        monitor.history_retention_secs = 100.0;
+        monitor.max_history_points = 20;
+        for t in 0..=300 {
+            monitor.push_history_sample(sample(t as f64));
+            assert!(monitor.history.len() <= 20);
+        }
+
+        let kept = timestamps(&monitor);
+        // The newest 10 seconds at full resolution
+        assert_eq!(kept[kept.len() - 11..], (290..=300).map(f64::from).collect::<Vec<_>>()[..]);
+        // Older ones at least 9 s apart, still reaching back over the retention
+        let older = &kept[..kept.len() - 11];
+        assert!(older.windows(2).all(|w| w[1] - w[0] >= 9.0), "{:?}", kept);
+        assert!(older[0] <= 210.0, "{:?}", kept);
+    }
+
+    #[test]
+    fn downsample_leaves_short_series_alone() {
+        assert_eq!(downsample(Vec::new(), 10), Vec::<[f64; 2]>::new());
+        assert_eq!(downsample(vec![[0.0, 5.0]], 1), vec![[0.0, 5.0]]);
+        let points = vec![[0.0, 1.0], [1.0, 2.0], [2.0, 3.0]];
+        assert_eq!(downsample(points.clone(), 3), points);
+        assert_eq!(downsample(points.clone(), 0), points);
+    }
+
+    #[test]
+    fn downsample_keeps_the_peak_of_each_bucket() {
+        let points: Vec<[f64; 2]> = (0..10).map(|i| [i as f64, if i == 5 { 99.0 } else { i as f64 }]).collect();
+        // 10 points into at most 3: buckets of 4, 4 and 2
+        assert_eq!(downsample(points, 3), vec![[3.0, 3.0], [5.0, 99.0], [9.0, 9.0]]);
+    }
+
+    #[test]
+    fn sample_near_picks_the_closest_sample() {
+        let mut monitor = ResourceMonitor::new();
+        monitor.history.clear();
+        assert!(monitor.sample_near(5.0).is_none());
+
+        monitor.history.push_back(sample(10.0));
+        assert_eq!(monitor.sample_near(0.0).map(|s| s.timestamp), Some(10.0));
+        assert_eq!(monitor.sample_near(99.0).map(|s| s.timestamp), Some(10.0));
+
+        monitor.history.extend([sample(20.0), sample(30.0)]);
+        let near = |t: f64| monitor.sample_near(t).map(|s| s.timestamp);
+        assert_eq!(near(-5.0), Some(10.0));
+        assert_eq!(near(14.0), Some(10.0));
+        // Halfway goes to the earlier sample
+        assert_eq!(near(15.0), Some(10.0));
+        assert_eq!(near(16.0), Some(20.0));
+        assert_eq!(near(30.0), Some(30.0));
+        assert_eq!(near(1e9), Some(30.0));
+    }
+}
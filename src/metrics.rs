@@ -0,0 +1,376 @@
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use sysinfo::System;
+
+use crate::{DiskInfo, NetworkInfo, ProcessInfo, ResourceMonitor, SystemTotals};
+
+// Default address of the embedded /metrics endpoint
+pub const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9184";
+
+// Everything one scrape or push exposes, captured at the end of an update
+pub struct MetricsSnapshot {
+    pub host: String,
+    pub totals: SystemTotals,
+    pub cpu_cores: Vec<f32>,
+    pub disks: Vec<DiskInfo>,
+    pub networks: Vec<NetworkInfo>,
+    pub processes: Vec<ProcessInfo>,
+}
+
+// Name, type, help text and value accessor of a labelled metric family
+type Metric<T> = (&'static str, &'static str, &'static str, fn(&T) -> f64);
+
+// Escapes a label value as required by the text exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    write_header(out, name, "gauge", help);
+    write_sample(out, name, &[], value);
+}
+
+// Renders the snapshot in Prometheus text format (version 0.0.4)
+pub fn render(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+    let totals = &snapshot.totals;
+
+    write_gauge(&mut out, "syswatch_cpu_usage_percent", "Global CPU usage.", totals.cpu_usage as f64);
+
+    write_header(&mut out, "syswatch_cpu_core_usage_percent", "gauge", "CPU usage per logical core.");
+    for (index, usage) in snapshot.cpu_cores.iter().enumerate() {
+        let core = index.to_string();
+        write_sample(&mut out, "syswatch_cpu_core_usage_percent", &[("core", &core)], *usage as f64);
+    }
+
+    write_gauge(&mut out, "syswatch_memory_total_bytes", "Total physical memory.", totals.total_memory as f64);
+    write_gauge(&mut out, "syswatch_memory_used_bytes", "Used physical memory.", totals.used_memory as f64);
+    write_gauge(&mut out, "syswatch_memory_free_bytes", "Free physical memory.", totals.free_memory as f64);
+    write_gauge(&mut out, "syswatch_swap_total_bytes", "Total swap space.", totals.total_swap as f64);
+    write_gauge(&mut out, "syswatch_swap_used_bytes", "Used swap space.", totals.used_swap as f64);
+    write_gauge(&mut out, "syswatch_process_count", "Number of running processes.", totals.process_count as f64);
+    write_gauge(&mut out, "syswatch_uptime_seconds", "System uptime.", totals.uptime as f64);
+
+    let disk_metrics: [Metric<DiskInfo>; 4] = [
+        ("syswatch_disk_total_bytes", "gauge", "Disk capacity.", |d| d.total_space as f64),
+        ("syswatch_disk_available_bytes", "gauge", "Disk space available.", |d| d.available_space as f64),
+        ("syswatch_disk_used_bytes", "gauge", "Disk space used.", |d| d.used_space as f64),
+        ("syswatch_disk_usage_percent", "gauge", "Disk space used as a percentage of capacity.", |d| d.usage_percent as f64),
+    ];
+    for (name, kind, help, value) in disk_metrics {
+        write_header(&mut out, name, kind, help);
+        for disk in &snapshot.disks {
+            let labels = [
+                ("device", disk.name.as_str()),
+                ("mount_point", disk.mount_point.as_str()),
+                ("fs", disk.file_system.as_str()),
+                ("type", disk.disk_type.as_str()),
+            ];
+            write_sample(&mut out, name, &labels, value(disk));
+        }
+    }
+
+    let network_metrics: [Metric<NetworkInfo>; 4] = [
+        ("syswatch_network_receive_bytes_per_second", "gauge", "Receive rate over the last update interval.", |n| n.received_per_sec),
+        ("syswatch_network_transmit_bytes_per_second", "gauge", "Transmit rate over the last update interval.", |n| n.transmitted_per_sec),
+        ("syswatch_network_receive_bytes_total", "counter", "Bytes received since boot.", |n| n.total_received as f64),
+        ("syswatch_network_transmit_bytes_total", "counter", "Bytes transmitted since boot.", |n| n.total_transmitted as f64),
+    ];
+    for (name, kind, help, value) in network_metrics {
+        write_header(&mut out, name, kind, help);
+        for network in &snapshot.networks {
+            write_sample(&mut out, name, &[("interface", &network.name)], value(network));
+        }
+    }
+
+    let process_metrics: [Metric<ProcessInfo>; 2] = [
+        ("syswatch_process_cpu_usage_percent", "gauge", "CPU usage of the top processes.", |p| p.cpu_usage as f64),
+        ("syswatch_process_memory_bytes", "gauge", "Resident memory of the top processes.", |p| p.memory_usage as f64),
+    ];
+    for (name, kind, help, value) in process_metrics {
+        write_header(&mut out, name, kind, help);
+        for process in &snapshot.processes {
+            let pid = process.pid.to_string();
+            let labels = [
+                ("pid", pid.as_str()),
+                ("name", process.name.as_str()),
+                ("user", process.user.as_deref().unwrap_or("")),
+            ];
+            write_sample(&mut out, name, &labels, value(process));
+        }
+    }
+
+    out
+}
+
+// Top N processes by CPU together with the top N by memory
+fn top_processes(all: &[ProcessInfo], count: usize) -> Vec<ProcessInfo> {
+    let mut by_cpu: Vec<&ProcessInfo> = all.iter().collect();
+    by_cpu.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    let mut by_memory: Vec<&ProcessInfo> = all.iter().collect();
+    by_memory.sort_by_key(|p| std::cmp::Reverse(p.memory_usage));
+
+    let mut top: Vec<ProcessInfo> = Vec::with_capacity(count * 2);
+    for process in by_cpu.into_iter().take(count).chain(by_memory.into_iter().take(count)) {
+        if !top.iter().any(|p| p.pid == process.pid) {
+            top.push(process.clone());
+        }
+    }
+    top
+}
+
+// Embedded HTTP server answering scrapes with the last published page
+pub struct MetricsServer {
+    addr: String,
+    server: Arc<tiny_http::Server>,
+    page: Arc<Mutex<String>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    pub fn start(addr: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let server = Arc::new(tiny_http::Server::http(addr)?);
+        let page = Arc::new(Mutex::new(String::new()));
+
+        let thread = {
+            let server = Arc::clone(&server);
+            let page = Arc::clone(&page);
+            std::thread::Builder::new()
+                .name("metrics-server".to_string())
+                .spawn(move || serve(&server, &page))?
+        };
+
+        // Reports the actual port when asked to listen on port 0
+        let bound = server.server_addr().to_ip().map(|ip| ip.to_string()).unwrap_or_else(|| addr.to_string());
+        Ok(Self {
+            addr: bound,
+            server,
+            page,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub fn publish(&self, page: String) {
+        if let Ok(mut current) = self.page.lock() {
+            *current = page;
+        }
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(server: &tiny_http::Server, page: &Mutex<String>) {
+    for request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or("");
+        let response = match path {
+            "/metrics" => {
+                let body = page.lock().map(|p| p.clone()).unwrap_or_default();
+                let content_type = tiny_http::Header::from_bytes(
+                    &b"Content-Type"[..],
+                    &b"text/plain; version=0.0.4; charset=utf-8"[..],
+                )
+                .expect("static header");
+                tiny_http::Response::from_string(body).with_header(content_type)
+            }
+            "/" => tiny_http::Response::from_string("SysWatch exporter\nMetrics are served at /metrics\n"),
+            _ => tiny_http::Response::from_string("Not found\n").with_status_code(404),
+        };
+        if let Err(e) = request.respond(response) {
+            eprintln!("Error answering metrics request: {}", e);
+        }
+    }
+}
+
+impl ResourceMonitor {
+    pub(crate) fn start_metrics_server(&mut self) {
+        match MetricsServer::start(&self.metrics_addr) {
+            Ok(server) => {
+                self.metrics_server = Some(server);
+                self.metrics_error = None;
+                self.publish_metrics(&self.metrics_snapshot(&self.all_processes()));
+            }
+            Err(e) => {
+                eprintln!("Error starting metrics server on {}: {}", self.metrics_addr, e);
+                self.metrics_error = Some(format!("Could not listen on {}: {}", self.metrics_addr, e));
+            }
+        }
+    }
+
+    pub(crate) fn stop_metrics_server(&mut self) {
+        self.metrics_server = None;
+    }
+
+    pub(crate) fn metrics_snapshot(&self, processes: &[ProcessInfo]) -> MetricsSnapshot {
+        MetricsSnapshot {
+            host: System::host_name().unwrap_or_default(),
+            totals: self.system_totals(),
+            cpu_cores: self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+            disks: self.disks.clone(),
+            networks: self.network_info.clone(),
+            processes: top_processes(processes, self.metrics_top_n),
+        }
+    }
+
+    pub(crate) fn publish_metrics(&self, snapshot: &MetricsSnapshot) {
+        if let Some(server) = &self.metrics_server {
+            server.publish(render(snapshot));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn process(pid: u32, cpu_usage: f32, memory_usage: u64) -> ProcessInfo {
+        ProcessInfo { pid, name: format!("p{}", pid), cpu_usage, memory_usage, ..Default::default() }
+    }
+
+    fn snapshot() -> MetricsSnapshot {
+        MetricsSnapshot {
+            host: "box".to_string(),
+            totals: SystemTotals {
+                timestamp: 1_700_000_000.0,
+                cpu_usage: 12.5,
+                total_memory: 8192,
+                used_memory: 4096,
+                free_memory: 4096,
+                total_swap: 0,
+                used_swap: 0,
+                process_count: 2,
+                uptime: 60,
+            },
+            cpu_cores: vec![10.0, 15.0],
+            disks: vec![DiskInfo {
+                name: "/dev/sda1".to_string(),
+                mount_point: "C:\\".to_string(),
+                total_space: 1000,
+                available_space: 250,
+                used_space: 750,
+                usage_percent: 75.0,
+                disk_type: "SSD".to_string(),
+                file_system: "ntfs".to_string(),
+            }],
+            networks: vec![NetworkInfo {
+                name: "eth0".to_string(),
+                received_per_sec: 1.5,
+                transmitted_per_sec: 0.0,
+                total_received: 10,
+                total_transmitted: 20,
+            }],
+            processes: vec![ProcessInfo {
+                user: Some("root".to_string()),
+                ..process(42, 3.5, 2048)
+            }, ProcessInfo {
+                name: "say \"hi\"\\\nbye".to_string(),
+                ..process(7, 0.0, 1)
+            }],
+        }
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        assert_eq!(escape_label("plain"), "plain");
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn renders_headers_and_samples() {
+        let page = render(&snapshot());
+
+        assert!(page.starts_with(
+            "# HELP syswatch_cpu_usage_percent Global CPU usage.\n\
+             # TYPE syswatch_cpu_usage_percent gauge\n\
+             syswatch_cpu_usage_percent 12.5\n"
+        ), "{}", page);
+        for line in [
+            "syswatch_cpu_core_usage_percent{core=\"1\"} 15",
+            "syswatch_memory_used_bytes 4096",
+            "syswatch_disk_usage_percent{device=\"/dev/sda1\",mount_point=\"C:\\\\\",fs=\"ntfs\",type=\"SSD\"} 75",
+            "# TYPE syswatch_network_receive_bytes_total counter",
+            "syswatch_network_receive_bytes_per_second{interface=\"eth0\"} 1.5",
+            "syswatch_process_cpu_usage_percent{pid=\"42\",name=\"p42\",user=\"root\"} 3.5",
+            "syswatch_process_memory_bytes{pid=\"7\",name=\"say \\\"hi\\\"\\\\\\nbye\",user=\"\"} 1",
+        ] {
+            assert!(page.lines().any(|l| l == line), "missing {}\n{}", line, page);
+        }
+
+        // Every family has HELP then TYPE once, before its samples
+        let mut declared = HashSet::new();
+        let lines: Vec<&str> = page.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let name = rest.split(' ').next().unwrap();
+                assert!(lines[index - 1].starts_with(&format!("# HELP {} ", name)), "{}", line);
+                assert!(declared.insert(name), "{} declared twice", name);
+            } else if !line.starts_with('#') {
+                let name = line.split(['{', ' ']).next().unwrap();
+                assert!(declared.contains(name), "{} has no TYPE", name);
+            }
+        }
+    }
+
+    #[test]
+    fn top_processes_combines_cpu_and_memory() {
+        let all = [process(1, 5.0, 10), process(2, 50.0, 20), process(3, 0.5, 900), process(4, 20.0, 800), process(5, 20.0, 1)];
+        let pids = |top: Vec<ProcessInfo>| top.iter().map(|p| p.pid).collect::<Vec<_>>();
+
+        assert_eq!(pids(top_processes(&all, 2)), [2, 4, 3]);
+        // Equal CPU keeps the input order
+        assert_eq!(pids(top_processes(&all, 3)), [2, 4, 5, 3]);
+        assert!(top_processes(&all, 0).is_empty());
+        assert!(top_processes(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn serves_the_published_page() {
+        let server = MetricsServer::start("127.0.0.1:0").unwrap();
+        assert!(!server.addr().ends_with(":0"));
+        let page = render(&snapshot());
+        server.publish(page.clone());
+
+        let response = ureq::get(&format!("http://{}/metrics", server.addr())).call().unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.header("Content-Type"), Some("text/plain; version=0.0.4; charset=utf-8"));
+        assert_eq!(response.into_string().unwrap(), page);
+
+        match ureq::get(&format!("http://{}/other", server.addr())).call() {
+            Err(ureq::Error::Status(code, _)) => assert_eq!(code, 404),
+            _ => panic!("expected 404"),
+        }
+    }
+}
@@ -0,0 +1,635 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sysinfo::System;
+
+use crate::alerts::{Alert, AlertEvent};
+use crate::{format_timestamp, unix_timestamp, ResourceMonitor};
+
+// A notification command still running after this long is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelKind {
+    // Native notification through the desktop's notification service
+    Desktop,
+    // JSON payload POSTed to a URL
+    Webhook { url: String },
+    // Shell command, alert details are passed as SYSWATCH_* environment variables
+    Command { command: String },
+}
+
+impl ChannelKind {
+    fn label(&self) -> &'static str {
+        match self {
+            ChannelKind::Desktop => "Desktop notification",
+            ChannelKind::Webhook { .. } => "Webhook",
+            ChannelKind::Command { .. } => "Command",
+        }
+    }
+}
+
+fn default_min_interval() -> f64 {
+    60.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChannelConfig {
+    pub enabled: bool,
+    pub kind: ChannelKind,
+    // Notifications for a rule and subject arriving sooner than this after the previous one are dropped
+    #[serde(default = "default_min_interval")]
+    pub min_interval_secs: f64,
+    #[serde(default = "default_true")]
+    pub notify_resolved: bool,
+}
+
+impl ChannelConfig {
+    fn new(kind: ChannelKind) -> Self {
+        Self {
+            enabled: true,
+            kind,
+            min_interval_secs: default_min_interval(),
+            notify_resolved: true,
+        }
+    }
+}
+
+// What every channel delivers; this is also the webhook body
+#[derive(Serialize, Clone)]
+pub struct Notification {
+    // "fired", "resolved" or "test"
+    pub event: &'static str,
+    pub host: String,
+    pub message: String,
+    pub alert: Alert,
+    // Notifications dropped by rate limiting since the previous one went out
+    pub suppressed: u64,
+}
+
+impl Notification {
+    fn title(&self) -> String {
+        match self.event {
+            "fired" => format!("SysWatch alert: {}", self.alert.rule_name),
+            "resolved" => format!("SysWatch resolved: {}", self.alert.rule_name),
+            _ => "SysWatch test notification".to_string(),
+        }
+    }
+
+    fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let alert = &self.alert;
+        vec![
+            ("SYSWATCH_EVENT", self.event.to_string()),
+            ("SYSWATCH_HOST", self.host.clone()),
+            ("SYSWATCH_MESSAGE", self.message.clone()),
+            ("SYSWATCH_RULE", alert.rule_name.clone()),
+            ("SYSWATCH_CONDITION", alert.condition.clone()),
+            ("SYSWATCH_SUBJECT", alert.subject.clone()),
+            ("SYSWATCH_PID", alert.pid.map(|pid| pid.to_string()).unwrap_or_default()),
+            ("SYSWATCH_VALUE", format!("{:.2}", alert.value)),
+            ("SYSWATCH_PEAK", format!("{:.2}", alert.peak)),
+            ("SYSWATCH_UNIT", alert.unit.trim().to_string()),
+            ("SYSWATCH_STARTED_AT", format_timestamp(alert.started_at, "%Y-%m-%dT%H:%M:%S%:z")),
+            ("SYSWATCH_RESOLVED_AT", alert.resolved_at
+                .map(|t| format_timestamp(t, "%Y-%m-%dT%H:%M:%S%:z"))
+                .unwrap_or_default()),
+            ("SYSWATCH_SUPPRESSED", self.suppressed.to_string()),
+        ]
+    }
+}
+
+fn notification(event: &'static str, alert: Alert) -> Notification {
+    let message = match event {
+        "resolved" => format!("Resolved: {}", alert.summary()),
+        _ => alert.summary(),
+    };
+    Notification {
+        event,
+        host: System::host_name().unwrap_or_default(),
+        message,
+        alert,
+        suppressed: 0,
+    }
+}
+
+fn test_notification() -> Notification {
+    let now = unix_timestamp();
+    let mut test = notification("test", Alert {
+        id: 0,
+        rule_id: 0,
+        rule_name: "Test alert".to_string(),
+        condition: "Global CPU > 90 %".to_string(),
+        subject: String::new(),
+        pid: None,
+        started_at: now,
+        resolved_at: None,
+        value: 95.0,
+        peak: 95.0,
+        unit: " %".to_string(),
+    });
+    test.message = "This is a test notification from SysWatch".to_string();
+    test
+}
+
+fn send(agent: &ureq::Agent, kind: &ChannelKind, notification: &Notification) -> Result<(), String> {
+    match kind {
+        ChannelKind::Desktop => notify_rust::Notification::new()
+            .appname("SysWatch")
+            .summary(&notification.title())
+            .body(&notification.message)
+            .show()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        ChannelKind::Webhook { url } => {
+            let body = serde_json::to_string(notification).map_err(|e| e.to_string())?;
+            agent.post(url)
+                .set("Content-Type", "application/json")
+                .send_string(&body)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        ChannelKind::Command { command } => run_command(command, notification),
+    }
+}
+
+fn run_command(command: &str, notification: &Notification) -> Result<(), String> {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.args(["/C", command]);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.args(["-c", command]);
+        process
+    };
+    let mut child = process
+        .envs(notification.env_vars())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => return Err(format!("command exited with {}", status)),
+            None if started.elapsed() > COMMAND_TIMEOUT => {
+                let _ = child.kill();
+                return Err(format!("command killed after {}s", COMMAND_TIMEOUT.as_secs()));
+            }
+            None => std::thread::sleep(Duration::from_millis(100)),
+        }
+    }
+}
+
+// Rate limiting state of one rule and subject on a channel
+#[derive(Default)]
+struct Throttle {
+    last_sent: Option<Instant>,
+    suppressed: u64,
+    // The latest firing went out, so its resolution has to as well
+    fired_delivered: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct ChannelStatus {
+    pub sent: u64,
+    pub suppressed: u64,
+    pub failures: u64,
+    pub last_sent: Option<f64>,
+    pub last_error: Option<String>,
+}
+
+// A running channel: delivery happens on a worker thread so slow endpoints never stall updates
+pub struct Notifier {
+    pub config: ChannelConfig,
+    sender: mpsc::Sender<Notification>,
+    status: Arc<Mutex<ChannelStatus>>,
+    // Keyed by rule id and subject so one noisy rule doesn't silence the others
+    throttles: HashMap<(u64, String), Throttle>,
+}
+
+impl Notifier {
+    pub fn start(config: ChannelConfig) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Notification>();
+        let status = Arc::new(Mutex::new(ChannelStatus::default()));
+
+        {
+            let kind = config.kind.clone();
+            let status = Arc::clone(&status);
+            // The thread ends by itself once the notifier is dropped and the channel closes
+            std::thread::Builder::new()
+                .name("notifier".to_string())
+                .spawn(move || {
+                    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(5)).build();
+                    for notification in receiver.iter() {
+                        let result = send(&agent, &kind, &notification);
+                        let Ok(mut status) = status.lock() else {
+                            return;
+                        };
+                        match result {
+                            Ok(()) => {
+                                status.sent += 1;
+                                status.last_sent = Some(unix_timestamp());
+                                status.last_error = None;
+                            }
+                            Err(e) => {
+                                eprintln!("Error sending {}: {}", kind.label(), e);
+                                status.failures += 1;
+                                status.last_error = Some(e);
+                            }
+                        }
+                    }
+                })?;
+        }
+
+        Ok(Self {
+            config,
+            sender,
+            status,
+            throttles: HashMap::new(),
+        })
+    }
+
+    // Sends a firing unless the same rule and subject already sent something within the minimum
+    // interval; a resolution goes out exactly when its firing did
+    fn notify(&mut self, mut notification: Notification) {
+        let interval = Duration::from_secs_f64(self.config.min_interval_secs.max(0.0));
+        // Forget subjects that are quiet again so the map doesn't grow with every process seen
+        self.throttles.retain(|_, throttle| {
+            throttle.fired_delivered
+                || throttle.suppressed > 0
+                || throttle.last_sent.is_some_and(|last| last.elapsed() < interval)
+        });
+
+        let key = (notification.alert.rule_id, notification.alert.subject.clone());
+        let throttle = self.throttles.entry(key).or_default();
+        let send = match notification.event {
+            "resolved" => std::mem::take(&mut throttle.fired_delivered),
+            _ => {
+                let send = !throttle.last_sent.is_some_and(|last| last.elapsed() < interval);
+                throttle.fired_delivered = send;
+                send
+            }
+        };
+        if !send {
+            throttle.suppressed += 1;
+            if let Ok(mut status) = self.status.lock() {
+                status.suppressed += 1;
+            }
+            return;
+        }
+        notification.suppressed = std::mem::take(&mut throttle.suppressed);
+        throttle.last_sent = Some(Instant::now());
+        let _ = self.sender.send(notification);
+    }
+
+    // Test sends bypass the rate limit
+    fn send_test(&self) {
+        let _ = self.sender.send(test_notification());
+    }
+
+    pub fn status(&self) -> ChannelStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+impl ResourceMonitor {
+    pub(crate) fn start_notifiers(&mut self) {
+        self.notifiers.clear();
+        self.test_notifiers.clear();
+        for config in self.alert_config.channels.iter().filter(|c| c.enabled) {
+            match Notifier::start(config.clone()) {
+                Ok(notifier) => self.notifiers.push(notifier),
+                Err(e) => eprintln!("Error starting {} channel: {}", config.kind.label(), e),
+            }
+        }
+    }
+
+    pub(crate) fn send_notifications(&mut self, event: &AlertEvent) {
+        for notifier in &mut self.notifiers {
+            match event {
+                AlertEvent::Fired(alert) => notifier.notify(notification("fired", alert.clone())),
+                AlertEvent::Resolved(alert) if notifier.config.notify_resolved => {
+                    notifier.notify(notification("resolved", alert.clone()))
+                }
+                AlertEvent::Resolved(_) => {}
+            }
+        }
+    }
+
+    // Sends a test through the running channel with these settings, or a temporary one
+    fn test_channel(&mut self, config: &ChannelConfig) {
+        if let Some(notifier) = self.notifiers.iter().find(|n| n.config == *config) {
+            notifier.send_test();
+            return;
+        }
+        if let Some(notifier) = self.test_notifiers.iter().find(|n| n.config == *config) {
+            notifier.send_test();
+            return;
+        }
+        match Notifier::start(config.clone()) {
+            Ok(notifier) => {
+                notifier.send_test();
+                self.test_notifiers.push(notifier);
+            }
+            Err(e) => eprintln!("Error starting {} channel: {}", config.kind.label(), e),
+        }
+    }
+
+    fn channel_status(&self, config: &ChannelConfig) -> Option<ChannelStatus> {
+        self.notifiers
+            .iter()
+            .chain(self.test_notifiers.iter())
+            .find(|n| n.config == *config)
+            .map(|n| n.status())
+    }
+
+    pub(crate) fn render_channel_editor(&mut self, ui: &mut egui::Ui) {
+        let mut remove = None;
+        let mut test = None;
+
+        ui.label(egui::RichText::new("Channels are told about every alert that fires or resolves")
+            .color(ui.visuals().weak_text_color()));
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .max_height(ui.available_height() - 70.0)
+            .show(ui, |ui| {
+                for index in 0..self.alert_config.channels.len() {
+                    let status = self.channel_status(&self.alert_config.channels[index]);
+                    let config = &mut self.alert_config.channels[index];
+                    ui.push_id(index, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut config.enabled, egui::RichText::new(config.kind.label()).strong());
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.small_button("Remove").clicked() {
+                                    remove = Some(index);
+                                }
+                                if ui.small_button("Send test").clicked() {
+                                    test = Some(config.clone());
+                                }
+                            });
+                        });
+
+                        egui::Grid::new("channel_grid")
+                            .num_columns(2)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                match &mut config.kind {
+                                    ChannelKind::Desktop => {}
+                                    ChannelKind::Webhook { url } => {
+                                        ui.label("URL: ");
+                                        ui.add(egui::TextEdit::singleline(url).desired_width(360.0));
+                                        ui.end_row();
+                                    }
+                                    ChannelKind::Command { command } => {
+                                        ui.label("Command: ");
+                                        ui.add(egui::TextEdit::singleline(command).desired_width(360.0))
+                                            .on_hover_text("Run by the shell with SYSWATCH_EVENT, SYSWATCH_RULE, SYSWATCH_SUBJECT, \
+                                                SYSWATCH_PID, SYSWATCH_VALUE, SYSWATCH_MESSAGE and more set");
+                                        ui.end_row();
+                                    }
+                                }
+                                ui.label("At most every: ");
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(&mut config.min_interval_secs)
+                                        .clamp_range(0.0..=86_400.0)
+                                        .suffix(" s"));
+                                    ui.checkbox(&mut config.notify_resolved, "Also when resolved");
+                                });
+                                ui.end_row();
+                            });
+
+                        if let Some(status) = status {
+                            let last = status.last_sent
+                                .map(|t| format_timestamp(t, "%H:%M:%S"))
+                                .unwrap_or_else(|| "never".to_string());
+                            ui.label(egui::RichText::new(format!(
+                                "Sent: {}  Rate limited: {}  Failures: {}  Last sent: {}",
+                                status.sent, status.suppressed, status.failures, last))
+                                .color(ui.visuals().weak_text_color()));
+                            if let Some(error) = status.last_error {
+                                ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+                            }
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+
+        if let Some(index) = remove {
+            self.alert_config.channels.remove(index);
+        }
+        if let Some(config) = test {
+            self.test_channel(&config);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Add: ");
+            if ui.button("Desktop").clicked() {
+                self.alert_config.channels.push(ChannelConfig::new(ChannelKind::Desktop));
+            }
+            if ui.button("Webhook").clicked() {
+                self.alert_config.channels.push(ChannelConfig::new(ChannelKind::Webhook {
+                    url: "http://localhost:8080/alerts".to_string(),
+                }));
+            }
+            if ui.button("Command").clicked() {
+                let command = if cfg!(windows) {
+                    "echo %SYSWATCH_MESSAGE% >> syswatch_alerts.txt"
+                } else {
+                    "echo \"$SYSWATCH_MESSAGE\" >> syswatch_alerts.txt"
+                };
+                self.alert_config.channels.push(ChannelConfig::new(ChannelKind::Command {
+                    command: command.to_string(),
+                }));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    fn alert(rule_id: u64, subject: &str) -> Alert {
+        Alert {
+            id: 7,
+            rule_id,
+            rule_name: "Process memory".to_string(),
+            condition: "Process memory [java] > 4096 MB".to_string(),
+            subject: subject.to_string(),
+            pid: Some(4242),
+            started_at: 1_700_000_000.0,
+            resolved_at: None,
+            value: 5000.0,
+            peak: 5120.5,
+            unit: " MB".to_string(),
+        }
+    }
+
+    // A notifier without a worker; what it would deliver arrives on the receiver
+    fn notifier(min_interval_secs: f64) -> (Notifier, mpsc::Receiver<Notification>) {
+        let (sender, receiver) = mpsc::channel();
+        let notifier = Notifier {
+            config: ChannelConfig { min_interval_secs, ..ChannelConfig::new(ChannelKind::Desktop) },
+            sender,
+            status: Arc::default(),
+            throttles: HashMap::new(),
+        };
+        (notifier, receiver)
+    }
+
+    fn delivered(receiver: &mpsc::Receiver<Notification>) -> Vec<(&'static str, u64, String, u64)> {
+        receiver
+            .try_iter()
+            .map(|n| (n.event, n.alert.rule_id, n.alert.subject, n.suppressed))
+            .collect()
+    }
+
+    #[test]
+    fn rate_limit_applies_per_rule_and_subject() {
+        let (mut notifier, receiver) = notifier(60.0);
+        notifier.notify(notification("fired", alert(1, "java (1)")));
+        notifier.notify(notification("fired", alert(1, "java (1)")));
+        notifier.notify(notification("fired", alert(1, "java (2)")));
+        notifier.notify(notification("fired", alert(2, "java (1)")));
+
+        assert_eq!(delivered(&receiver), [
+            ("fired", 1, "java (1)".to_string(), 0),
+            ("fired", 1, "java (2)".to_string(), 0),
+            ("fired", 2, "java (1)".to_string(), 0),
+        ]);
+        assert_eq!(notifier.status().suppressed, 1);
+    }
+
+    #[test]
+    fn resolutions_follow_their_firing() {
+        let (mut notifier, receiver) = notifier(60.0);
+        notifier.notify(notification("fired", alert(1, "")));
+        // Right after the firing, still within the interval
+        notifier.notify(notification("resolved", alert(1, "")));
+        // The flap that follows is held back, and so is its resolution
+        notifier.notify(notification("fired", alert(1, "")));
+        notifier.notify(notification("resolved", alert(1, "")));
+        assert_eq!(delivered(&receiver), [("fired", 1, String::new(), 0), ("resolved", 1, String::new(), 0)]);
+
+        // Once the interval has passed the next firing reports what was dropped
+        notifier.throttles.get_mut(&(1, String::new())).unwrap().last_sent = Some(Instant::now() - Duration::from_secs(61));
+        notifier.notify(notification("fired", alert(1, "")));
+        assert_eq!(delivered(&receiver), [("fired", 1, String::new(), 2)]);
+        assert_eq!(notifier.status().suppressed, 2);
+    }
+
+    #[test]
+    fn zero_interval_sends_everything() {
+        let (mut notifier, receiver) = notifier(0.0);
+        for _ in 0..3 {
+            notifier.notify(notification("fired", alert(1, "")));
+            notifier.notify(notification("resolved", alert(1, "")));
+        }
+        assert_eq!(delivered(&receiver).len(), 6);
+        assert_eq!(notifier.status().suppressed, 0);
+    }
+
+    #[test]
+    fn webhook_posts_the_notification_as_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/alerts", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim_end().is_empty() {
+                    break;
+                }
+                head.push(line.trim_end().to_ascii_lowercase());
+            }
+            let length: usize = head
+                .iter()
+                .find_map(|line| line.strip_prefix("content-length: "))
+                .unwrap()
+                .parse()
+                .unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader.get_mut().write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+            (head, body)
+        });
+
+        let config = ChannelConfig::new(ChannelKind::Webhook { url });
+        let mut notifier = Notifier::start(config).unwrap();
+        notifier.notify(notification("fired", alert(3, "java (4242)")));
+        let (head, body) = server.join().unwrap();
+
+        assert_eq!(head[0], "post /alerts http/1.1");
+        assert!(head.contains(&"content-type: application/json".to_string()), "{:?}", head);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["event"], "fired");
+        assert_eq!(json["message"], "Process memory on java (4242): 5000.0 MB (Process memory [java] > 4096 MB)");
+        assert_eq!(json["suppressed"], 0);
+        assert_eq!(json["alert"]["rule_id"], 3);
+        assert_eq!(json["alert"]["subject"], "java (4242)");
+        assert_eq!(json["alert"]["pid"], 4242);
+        assert_eq!(json["alert"]["peak"], 5120.5);
+        assert!(json["alert"]["resolved_at"].is_null());
+        assert!(json["host"].is_string());
+    }
+
+    #[test]
+    fn command_environment_describes_the_alert() {
+        let mut resolved = alert(1, "java (4242)");
+        resolved.resolved_at = Some(1_700_000_060.0);
+        let mut notification = notification("resolved", resolved);
+        notification.suppressed = 3;
+        let vars: HashMap<&str, String> = notification.env_vars().into_iter().collect();
+
+        assert_eq!(vars["SYSWATCH_EVENT"], "resolved");
+        assert_eq!(vars["SYSWATCH_RULE"], "Process memory");
+        assert_eq!(vars["SYSWATCH_CONDITION"], "Process memory [java] > 4096 MB");
+        assert_eq!(vars["SYSWATCH_SUBJECT"], "java (4242)");
+        assert_eq!(vars["SYSWATCH_PID"], "4242");
+        assert_eq!(vars["SYSWATCH_VALUE"], "5000.00");
+        assert_eq!(vars["SYSWATCH_PEAK"], "5120.50");
+        assert_eq!(vars["SYSWATCH_UNIT"], "MB");
+        assert_eq!(vars["SYSWATCH_SUPPRESSED"], "3");
+        assert!(vars["SYSWATCH_MESSAGE"].starts_with("Resolved: "));
+        assert!(!vars["SYSWATCH_STARTED_AT"].is_empty());
+        assert!(!vars["SYSWATCH_RESOLVED_AT"].is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_run_with_the_alert_environment() {
+        let output = std::env::temp_dir().join(format!("syswatch_command_{}", std::process::id()));
+        let command = format!(
+            "printf '%s|%s|%s|%s' \"$SYSWATCH_EVENT\" \"$SYSWATCH_RULE\" \"$SYSWATCH_SUBJECT\" \"$SYSWATCH_PID\" > '{}'",
+            output.display()
+        );
+        run_command(&command, &notification("fired", alert(1, "java (4242)"))).unwrap();
+        let written = std::fs::read_to_string(&output).unwrap();
+        std::fs::remove_file(&output).unwrap();
+
+        assert_eq!(written, "fired|Process memory|java (4242)|4242");
+        assert_eq!(
+            run_command("exit 3", &test_notification()),
+            Err("command exited with exit status: 3".to_string())
+        );
+    }
+}
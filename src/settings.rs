@@ -0,0 +1,234 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::columns::{self, SortKey, TableColumn};
+use crate::grouping::GroupBy;
+use crate::theme::ThemeSettings;
+use crate::thresholds::ColorThresholds;
+use crate::{storage, ResourceMonitor};
+
+// File in the config directory holding the UI state
+pub(crate) const SETTINGS_FILE: &str = "settings.json";
+// Changes are written once they have settled for this long, so dragging a slider doesn't write every frame
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [1400.0, 900.0];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub maximized: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub update_interval: f32,
+    pub show_system_info: bool,
+    pub show_disk_info: bool,
+    pub show_users: bool,
+    pub show_charts: bool,
+    pub energy_saving_mode: bool,
+    pub row_height: f32,
+    pub sort_column: TableColumn,
+    pub sort_descending: bool,
+    pub then_by: Vec<SortKey>,
+    pub group_by: GroupBy,
+    // Shown columns in display order, and the width of every column
+    pub columns: Vec<TableColumn>,
+    pub column_widths: BTreeMap<TableColumn, f32>,
+    pub color_thresholds: ColorThresholds,
+    pub theme: ThemeSettings,
+    pub window: Option<WindowGeometry>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            update_interval: 1.0,
+            show_system_info: true,
+            show_disk_info: true,
+            show_users: false,
+            show_charts: true,
+            energy_saving_mode: false,
+            row_height: 25.0,
+            sort_column: TableColumn::Name,
+            sort_descending: false,
+            then_by: Vec::new(),
+            group_by: GroupBy::None,
+            columns: TableColumn::DEFAULT.to_vec(),
+            column_widths: columns::default_widths(),
+            color_thresholds: ColorThresholds::default(),
+            theme: ThemeSettings::default(),
+            window: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load_json(SETTINGS_FILE).unwrap_or_default()
+    }
+
+    // Window size and position to start with
+    pub fn viewport(&self, builder: egui::ViewportBuilder) -> egui::ViewportBuilder {
+        match self.window {
+            Some(window) => builder
+                .with_inner_size([window.width, window.height])
+                .with_position([window.x, window.y])
+                .with_maximized(window.maximized),
+            None => builder.with_inner_size(DEFAULT_WINDOW_SIZE),
+        }
+    }
+}
+
+// Reads the current window geometry; a maximized window keeps its last normal size
+fn window_geometry(ctx: &egui::Context, previous: Option<WindowGeometry>) -> Option<WindowGeometry> {
+    ctx.input(|i| {
+        let viewport = i.viewport();
+        let maximized = viewport.maximized.unwrap_or(false);
+        if maximized {
+            return previous.map(|window| WindowGeometry { maximized: true, ..window });
+        }
+        let inner = viewport.inner_rect?;
+        let outer = viewport.outer_rect?;
+        Some(WindowGeometry {
+            x: outer.min.x,
+            y: outer.min.y,
+            width: inner.width(),
+            height: inner.height(),
+            maximized: false,
+        })
+    })
+}
+
+impl ResourceMonitor {
+    pub(crate) fn apply_settings(&mut self, settings: &Settings) {
+        self.update_interval = settings.update_interval;
+        self.show_system_info = settings.show_system_info;
+        self.show_disk_info = settings.show_disk_info;
+        self.show_users = settings.show_users;
+        self.show_charts = settings.show_charts;
+        self.energy_saving_mode = settings.energy_saving_mode;
+        self.row_height = settings.row_height;
+        self.sort_column = settings.sort_column;
+        self.sort_descending = settings.sort_descending;
+        self.then_by = settings.then_by.clone();
+        self.group_by = settings.group_by;
+        self.set_columns(&settings.columns, &settings.column_widths);
+        self.color_thresholds = settings.color_thresholds.clone();
+        self.theme = settings.theme.clone();
+        self.saved_settings = settings.clone();
+    }
+
+    fn current_settings(&self, ctx: &egui::Context) -> Settings {
+        Settings {
+            update_interval: self.update_interval,
+            show_system_info: self.show_system_info,
+            show_disk_info: self.show_disk_info,
+            show_users: self.show_users,
+            show_charts: self.show_charts,
+            energy_saving_mode: self.energy_saving_mode,
+            row_height: self.row_height,
+            sort_column: self.sort_column,
+            sort_descending: self.sort_descending,
+            then_by: self.then_by.clone(),
+            group_by: self.group_by,
+            columns: self.visible_columns.clone(),
+            column_widths: self.column_widths.clone(),
+            color_thresholds: self.color_thresholds.clone(),
+            theme: self.theme.clone(),
+            window: window_geometry(ctx, self.saved_settings.window).or(self.saved_settings.window),
+        }
+    }
+
+    // Called every frame; writes the settings once they stop changing or when the window closes
+    pub(crate) fn persist_settings(&mut self, ctx: &egui::Context) {
+        let current = self.current_settings(ctx);
+        if current == self.saved_settings {
+            self.settings_changed_at = None;
+            return;
+        }
+
+        let closing = ctx.input(|i| i.viewport().close_requested());
+        let changed_at = *self.settings_changed_at.get_or_insert_with(Instant::now);
+        if !closing && changed_at.elapsed() < SAVE_DELAY {
+            ctx.request_repaint_after(SAVE_DELAY);
+            return;
+        }
+
+        if let Err(e) = storage::save_json(SETTINGS_FILE, &current) {
+            eprintln!("Error saving settings: {}", e);
+        }
+        self.saved_settings = current;
+        self.settings_changed_at = None;
+    }
+
+    pub(crate) fn reset_settings(&mut self, ctx: &egui::Context) {
+        self.apply_settings(&Settings::default());
+        self.sort_process_indices();
+        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(DEFAULT_WINDOW_SIZE.into()));
+        if let Err(e) = storage::save_json(SETTINGS_FILE, &self.saved_settings) {
+            eprintln!("Error saving settings: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thresholds::MemoryThresholdMode;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = Settings {
+            update_interval: 2.5,
+            show_users: true,
+            energy_saving_mode: true,
+            sort_column: TableColumn::Cpu,
+            sort_descending: true,
+            then_by: vec![SortKey { column: TableColumn::Name, descending: false }],
+            group_by: GroupBy::User,
+            columns: vec![TableColumn::Pid, TableColumn::Name, TableColumn::Cpu],
+            window: Some(WindowGeometry { x: 10.0, y: 20.0, width: 800.0, height: 600.0, maximized: true }),
+            ..Default::default()
+        };
+        settings.column_widths.insert(TableColumn::Name, 333.0);
+        settings.color_thresholds.memory_mode = MemoryThresholdMode::PercentOfRam;
+        settings.theme.custom_palette = true;
+        settings.theme.palette.accent = [1, 2, 3];
+
+        // The same serialization storage::save_json and load_json use
+        let json = serde_json::to_string_pretty(&settings).unwrap();
+        let loaded: Settings = serde_json::from_str(&json).unwrap();
+        assert!(loaded == settings);
+    }
+
+    #[test]
+    fn older_files_fill_in_defaults() {
+        // Written before columns, grouping, themes and window geometry existed
+        let json = r#"{
+            "update_interval": 3.0,
+            "show_charts": false,
+            "sort_column": "memory",
+            "color_thresholds": { "cpu_warning": 30.0 },
+            "removed_setting": 1
+        }"#;
+        let loaded: Settings = serde_json::from_str(json).unwrap();
+
+        let expected = Settings {
+            update_interval: 3.0,
+            show_charts: false,
+            sort_column: TableColumn::Memory,
+            color_thresholds: ColorThresholds { cpu_warning: 30.0, ..Default::default() },
+            ..Default::default()
+        };
+        assert!(loaded == expected);
+        assert!(serde_json::from_str::<Settings>("{}").unwrap() == Settings::default());
+    }
+}
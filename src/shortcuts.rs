@@ -0,0 +1,356 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::{actions, ResourceMonitor};
+
+// Id of the process search box, so a shortcut can focus it
+pub const SEARCH_FIELD_ID: &str = "process_search";
+// Typed letters older than this start a new type-ahead search
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShortcutAction {
+    MoveUp,
+    MoveDown,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    FocusSearch,
+    Terminate,
+    Refresh,
+    Help,
+    CommandPalette,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 11] = [
+        ShortcutAction::MoveUp,
+        ShortcutAction::MoveDown,
+        ShortcutAction::PageUp,
+        ShortcutAction::PageDown,
+        ShortcutAction::First,
+        ShortcutAction::Last,
+        ShortcutAction::FocusSearch,
+        ShortcutAction::Terminate,
+        ShortcutAction::Refresh,
+        ShortcutAction::Help,
+        ShortcutAction::CommandPalette,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShortcutAction::MoveUp => "Previous process",
+            ShortcutAction::MoveDown => "Next process",
+            ShortcutAction::PageUp => "One page up",
+            ShortcutAction::PageDown => "One page down",
+            ShortcutAction::First => "First process",
+            ShortcutAction::Last => "Last process",
+            ShortcutAction::FocusSearch => "Search processes",
+            ShortcutAction::Terminate => "Terminate the selected process",
+            ShortcutAction::Refresh => "Update now",
+            ShortcutAction::Help => "Show this list",
+            ShortcutAction::CommandPalette => "Command palette",
+        }
+    }
+
+    // Key under [shortcuts] in the config file
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            ShortcutAction::MoveUp => "move_up",
+            ShortcutAction::MoveDown => "move_down",
+            ShortcutAction::PageUp => "page_up",
+            ShortcutAction::PageDown => "page_down",
+            ShortcutAction::First => "first",
+            ShortcutAction::Last => "last",
+            ShortcutAction::FocusSearch => "focus_search",
+            ShortcutAction::Terminate => "terminate",
+            ShortcutAction::Refresh => "refresh",
+            ShortcutAction::Help => "help",
+            ShortcutAction::CommandPalette => "command_palette",
+        }
+    }
+}
+
+// Key combinations such as "Ctrl+F", "Delete" or "/", one per action
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub move_up: String,
+    pub move_down: String,
+    pub page_up: String,
+    pub page_down: String,
+    pub first: String,
+    pub last: String,
+    pub focus_search: String,
+    pub terminate: String,
+    pub refresh: String,
+    pub help: String,
+    pub command_palette: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            move_up: "ArrowUp".to_string(),
+            move_down: "ArrowDown".to_string(),
+            page_up: "PageUp".to_string(),
+            page_down: "PageDown".to_string(),
+            first: "Home".to_string(),
+            last: "End".to_string(),
+            focus_search: "/".to_string(),
+            terminate: "Delete".to_string(),
+            refresh: "F5".to_string(),
+            help: "F1".to_string(),
+            command_palette: "Ctrl+Shift+P".to_string(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, action: ShortcutAction) -> &str {
+        match action {
+            ShortcutAction::MoveUp => &self.move_up,
+            ShortcutAction::MoveDown => &self.move_down,
+            ShortcutAction::PageUp => &self.page_up,
+            ShortcutAction::PageDown => &self.page_down,
+            ShortcutAction::First => &self.first,
+            ShortcutAction::Last => &self.last,
+            ShortcutAction::FocusSearch => &self.focus_search,
+            ShortcutAction::Terminate => &self.terminate,
+            ShortcutAction::Refresh => &self.refresh,
+            ShortcutAction::Help => &self.help,
+            ShortcutAction::CommandPalette => &self.command_palette,
+        }
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut seen: Vec<egui::KeyboardShortcut> = Vec::new();
+        for action in ShortcutAction::ALL {
+            match parse_shortcut(self.get(action)) {
+                Ok(shortcut) if seen.contains(&shortcut) => errors.push(format!(
+                    "shortcuts.{}: \"{}\" is already bound to another action", action.config_key(), self.get(action))),
+                Ok(shortcut) => seen.push(shortcut),
+                Err(e) => errors.push(format!("shortcuts.{}: {}", action.config_key(), e)),
+            }
+        }
+        errors
+    }
+}
+
+// Parses "Ctrl+Shift+P"; key names are egui's ("ArrowUp", "Delete", "F5", "/")
+pub fn parse_shortcut(text: &str) -> Result<egui::KeyboardShortcut, String> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    // "Ctrl++" binds the plus key
+    if text.ends_with("++") {
+        parts.truncate(parts.len() - 2);
+        parts.push("+");
+    }
+    let key_name = parts.pop().filter(|name| !name.is_empty())
+        .ok_or_else(|| format!("\"{}\" has no key", text))?;
+    let key = egui::Key::from_name(key_name)
+        .ok_or_else(|| format!("\"{}\" is not a known key", key_name))?;
+
+    let mut modifiers = egui::Modifiers::NONE;
+    for part in parts {
+        match part.to_lowercase().as_str() {
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "shift" => modifiers.shift = true,
+            "alt" | "option" => modifiers.alt = true,
+            // Ctrl on Windows and Linux, Command on macOS
+            "cmd" | "command" => modifiers.command = true,
+            other => return Err(format!("\"{}\" is not a modifier (Ctrl, Shift, Alt, Cmd)", other)),
+        }
+    }
+    Ok(egui::KeyboardShortcut::new(modifiers, key))
+}
+
+fn modifier_count(shortcut: &egui::KeyboardShortcut) -> usize {
+    let m = shortcut.modifiers;
+    [m.ctrl, m.shift, m.alt, m.command].iter().filter(|&&on| on).count()
+}
+
+impl ResourceMonitor {
+    // Called once per frame before the panels are drawn
+    pub(crate) fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Keys belong to the search box or another text field while one is focused
+        if ctx.wants_keyboard_input() || self.confirm_kill.is_some() || self.show_command_palette {
+            return;
+        }
+
+        let mut bindings: Vec<(egui::KeyboardShortcut, ShortcutAction)> = ShortcutAction::ALL
+            .iter()
+            .filter_map(|&action| parse_shortcut(self.key_bindings.get(action)).ok().map(|s| (s, action)))
+            .collect();
+        // egui ignores extra Shift/Alt when matching, so try the most specific combinations first
+        bindings.sort_by_key(|(shortcut, _)| std::cmp::Reverse(modifier_count(shortcut)));
+
+        let mut handled = false;
+        for (shortcut, action) in bindings {
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                self.run_shortcut(ctx, action);
+                handled = true;
+            }
+        }
+        // The key of a shortcut like "/" also arrives as typed text
+        if handled {
+            return;
+        }
+
+        let typed: String = ctx.input(|i| {
+            if i.modifiers.ctrl || i.modifiers.command || i.modifiers.alt {
+                return String::new();
+            }
+            i.events.iter().filter_map(|event| match event {
+                egui::Event::Text(text) => Some(text.as_str()),
+                _ => None,
+            }).collect()
+        });
+        if !typed.trim().is_empty() {
+            self.type_ahead(&typed);
+        }
+    }
+
+    fn run_shortcut(&mut self, ctx: &egui::Context, action: ShortcutAction) {
+        let page = self.table_page_rows.max(1) as isize;
+        match action {
+            ShortcutAction::MoveUp => self.move_selection(-1),
+            ShortcutAction::MoveDown => self.move_selection(1),
+            ShortcutAction::PageUp => self.move_selection(-page),
+            ShortcutAction::PageDown => self.move_selection(page),
+            ShortcutAction::First => self.move_selection(isize::MIN),
+            ShortcutAction::Last => self.move_selection(isize::MAX),
+            ShortcutAction::FocusSearch => {
+                ctx.memory_mut(|m| m.request_focus(egui::Id::new(SEARCH_FIELD_ID)));
+            }
+            ShortcutAction::Terminate => {
+                if self.snapshot.is_none() {
+                    self.confirm_kill = self.selected;
+                }
+            }
+            ShortcutAction::Refresh => {
+                self.request_immediate_update();
+                ctx.request_repaint();
+            }
+            ShortcutAction::Help => self.show_shortcuts_window = !self.show_shortcuts_window,
+            ShortcutAction::CommandPalette => self.open_command_palette(ctx),
+        }
+    }
+
+    // Moves the selection by `delta` process rows in table order, stopping at either end;
+    // group rows are passed over
+    fn move_selection(&mut self, delta: isize) {
+        let rows: Vec<usize> = (0..self.table_rows.len())
+            .filter(|&position| self.table_rows[position].process().is_some())
+            .collect();
+        let Some(last) = rows.len().checked_sub(1) else {
+            return;
+        };
+        let current = self.selected
+            .and_then(|key| self.row_of(key))
+            .and_then(|row| rows.iter().position(|&r| r == row));
+        let target = match current {
+            Some(position) => (position as isize).saturating_add(delta).clamp(0, last as isize) as usize,
+            None if delta < 0 => last,
+            None => 0,
+        };
+        self.select_row(rows[target]);
+    }
+
+    fn select_row(&mut self, position: usize) {
+        if let Some(index) = self.table_rows[position].process() {
+            self.selected = Some(self.processes[index].key());
+            self.scroll_to_selected = true;
+        }
+    }
+
+    // Jumps to the first process whose name starts with the letters typed in quick succession
+    fn type_ahead(&mut self, typed: &str) {
+        let now = Instant::now();
+        if self.type_ahead_at.map_or(true, |at| now.duration_since(at) > TYPE_AHEAD_TIMEOUT) {
+            self.type_ahead_buffer.clear();
+        }
+        self.type_ahead_at = Some(now);
+        self.type_ahead_buffer.push_str(&typed.to_lowercase());
+
+        let prefix = &self.type_ahead_buffer;
+        if let Some(position) = self.table_rows.iter().position(|row| {
+            row.process().is_some_and(|i| self.processes[i].name.to_lowercase().starts_with(prefix.as_str()))
+        }) {
+            self.select_row(position);
+        }
+    }
+
+    pub(crate) fn render_kill_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(key) = self.confirm_kill else {
+            return;
+        };
+        let target = actions::describe_process(&self.system, key.pid);
+        let mut confirmed = ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter));
+        let mut cancelled = ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape));
+
+        egui::Window::new("Terminate process?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Terminate {}? Unsaved work in it will be lost.", target));
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    confirmed |= ui.button("Terminate (Enter)").clicked();
+                    cancelled |= ui.button("Cancel (Esc)").clicked();
+                });
+            });
+
+        if confirmed {
+            self.kill_process(key);
+        }
+        if confirmed || cancelled {
+            self.confirm_kill = None;
+        }
+    }
+
+    pub(crate) fn render_shortcuts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_shortcuts_window;
+        if open && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+            open = false;
+        }
+
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for action in ShortcutAction::ALL {
+                            let binding = self.key_bindings.get(action);
+                            let text = match parse_shortcut(binding) {
+                                Ok(shortcut) => ctx.format_shortcut(&shortcut),
+                                Err(_) => format!("{} (invalid)", binding),
+                            };
+                            ui.label(egui::RichText::new(text).monospace().strong())
+                                .on_hover_text(format!("shortcuts.{} in the config file", action.config_key()));
+                            ui.label(action.label());
+                            ui.end_row();
+                        }
+
+                        ui.label(egui::RichText::new("Letters").monospace().strong());
+                        ui.label("Jump to the first process whose name starts with them");
+                        ui.end_row();
+                        ui.label(egui::RichText::new("Enter / Esc").monospace().strong());
+                        ui.label("Confirm or cancel a termination");
+                        ui.end_row();
+                    });
+                ui.add_space(4.0);
+                ui.label(egui::RichText::new("Shortcuts are ignored while a text field has focus; Esc leaves it.")
+                    .color(ui.visuals().weak_text_color()));
+            });
+
+        self.show_shortcuts_window = open;
+    }
+}
@@ -0,0 +1,662 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::net::UdpSocket;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::metrics::MetricsSnapshot;
+use crate::theme::color;
+use crate::{storage, ResourceMonitor};
+
+// File in the config directory holding the sink list
+pub(crate) const SINKS_FILE: &str = "sinks.json";
+// Samples kept for retrying while a destination is unreachable
+const MAX_BUFFERED_SAMPLES: usize = 1000;
+// First retry delay, doubled on every further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+// Largest StatsD datagram that fits a typical MTU
+const MAX_DATAGRAM_BYTES: usize = 1432;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkKind {
+    // InfluxDB write API, e.g. http://localhost:8086/api/v2/write?org=o&bucket=b&precision=ns
+    InfluxHttp { url: String, token: String },
+    // Line protocol appended to a local file
+    InfluxFile { path: String },
+    // StatsD gauges, with DogStatsD tags when enabled
+    Statsd { addr: String, prefix: String, dogstatsd: bool },
+}
+
+impl SinkKind {
+    fn label(&self) -> &'static str {
+        match self {
+            SinkKind::InfluxHttp { .. } => "InfluxDB (HTTP)",
+            SinkKind::InfluxFile { .. } => "InfluxDB (file)",
+            SinkKind::Statsd { dogstatsd: true, .. } => "DogStatsD",
+            SinkKind::Statsd { .. } => "StatsD",
+        }
+    }
+}
+
+fn default_batch_size() -> usize {
+    1
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SinkConfig {
+    pub enabled: bool,
+    pub kind: SinkKind,
+    // Number of samples collected before they are sent together
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl SinkConfig {
+    fn new(kind: SinkKind) -> Self {
+        Self {
+            enabled: true,
+            kind,
+            batch_size: default_batch_size(),
+            max_retries: default_max_retries(),
+        }
+    }
+}
+
+// One measurement with its tags and fields, shared by all push formats
+#[derive(Clone)]
+pub struct MetricPoint {
+    measurement: &'static str,
+    tags: Vec<(&'static str, String)>,
+    fields: Vec<(&'static str, f64)>,
+    timestamp_ns: i64,
+}
+
+pub fn points(snapshot: &MetricsSnapshot) -> Vec<MetricPoint> {
+    let totals = &snapshot.totals;
+    let timestamp_ns = (totals.timestamp * 1000.0).round() as i64 * 1_000_000;
+    let host = ("host", snapshot.host.clone());
+    let point = |measurement, tags: Vec<(&'static str, String)>, fields| {
+        let mut all_tags = vec![host.clone()];
+        all_tags.extend(tags);
+        MetricPoint { measurement, tags: all_tags, fields, timestamp_ns }
+    };
+
+    let mut points = vec![point("system", Vec::new(), vec![
+        ("cpu_usage_percent", totals.cpu_usage as f64),
+        ("memory_total_bytes", totals.total_memory as f64),
+        ("memory_used_bytes", totals.used_memory as f64),
+        ("memory_free_bytes", totals.free_memory as f64),
+        ("swap_total_bytes", totals.total_swap as f64),
+        ("swap_used_bytes", totals.used_swap as f64),
+        ("process_count", totals.process_count as f64),
+        ("uptime_seconds", totals.uptime as f64),
+    ])];
+
+    for (index, usage) in snapshot.cpu_cores.iter().enumerate() {
+        points.push(point("cpu", vec![("core", index.to_string())], vec![
+            ("usage_percent", *usage as f64),
+        ]));
+    }
+
+    for disk in &snapshot.disks {
+        points.push(point("disk", vec![
+            ("device", disk.name.clone()),
+            ("mount_point", disk.mount_point.clone()),
+            ("fs", disk.file_system.clone()),
+            ("type", disk.disk_type.clone()),
+        ], vec![
+            ("total_bytes", disk.total_space as f64),
+            ("available_bytes", disk.available_space as f64),
+            ("used_bytes", disk.used_space as f64),
+            ("usage_percent", disk.usage_percent as f64),
+        ]));
+    }
+
+    for network in &snapshot.networks {
+        points.push(point("network", vec![("interface", network.name.clone())], vec![
+            ("receive_bytes_per_second", network.received_per_sec),
+            ("transmit_bytes_per_second", network.transmitted_per_sec),
+            ("receive_bytes_total", network.total_received as f64),
+            ("transmit_bytes_total", network.total_transmitted as f64),
+        ]));
+    }
+
+    for process in &snapshot.processes {
+        points.push(point("process", vec![
+            ("pid", process.pid.to_string()),
+            ("name", process.name.clone()),
+            ("user", process.user.clone().unwrap_or_default()),
+        ], vec![
+            ("cpu_usage_percent", process.cpu_usage as f64),
+            ("memory_bytes", process.memory_usage as f64),
+        ]));
+    }
+
+    points
+}
+
+fn escape_line_protocol(value: &str, extra: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == ',' || c == ' ' || c == '\\' || extra.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn to_line_protocol(points: &[&MetricPoint]) -> String {
+    let mut out = String::new();
+    for point in points {
+        // NaN and infinity are rejected by InfluxDB and would fail the whole batch
+        let fields: Vec<String> = point.fields
+            .iter()
+            .filter(|(_, value)| value.is_finite())
+            .map(|(key, value)| format!("{}={}", escape_line_protocol(key, &['=']), value))
+            .collect();
+        if fields.is_empty() {
+            continue;
+        }
+        out.push_str(&escape_line_protocol(&format!("syswatch_{}", point.measurement), &[]));
+        // Empty tag values are not allowed by the protocol
+        for (key, value) in point.tags.iter().filter(|(_, v)| !v.is_empty()) {
+            out.push(',');
+            out.push_str(&escape_line_protocol(key, &['=']));
+            out.push('=');
+            out.push_str(&escape_line_protocol(value, &['=']));
+        }
+        out.push(' ');
+        out.push_str(&fields.join(","));
+        out.push(' ');
+        out.push_str(&point.timestamp_ns.to_string());
+        out.push('\n');
+    }
+    out
+}
+
+// Keeps StatsD names and tags free of protocol separators
+fn sanitize_statsd(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+pub fn to_statsd_lines(points: &[&MetricPoint], prefix: &str, dogstatsd: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    for point in points {
+        // Plain StatsD has no tags, so they become part of the metric name
+        let (scope, tags) = if dogstatsd {
+            let tags: Vec<String> = point.tags
+                .iter()
+                .filter(|(_, v)| !v.is_empty())
+                .map(|(key, value)| format!("{}:{}", sanitize_statsd(key), sanitize_statsd(value)))
+                .collect();
+            let tags = if tags.is_empty() { String::new() } else { format!("|#{}", tags.join(",")) };
+            (String::new(), tags)
+        } else {
+            let scope: String = point.tags
+                .iter()
+                .filter(|(key, value)| *key != "host" && !value.is_empty())
+                .map(|(_, value)| format!(".{}", sanitize_statsd(value)))
+                .collect();
+            (scope, String::new())
+        };
+        let name = if prefix.is_empty() {
+            format!("{}{}", point.measurement, scope)
+        } else {
+            format!("{}.{}{}", prefix, point.measurement, scope)
+        };
+        for (field, value) in point.fields.iter().filter(|(_, value)| value.is_finite()) {
+            lines.push(format!("{}.{}:{}|g{}", name, field, value, tags));
+        }
+    }
+    lines
+}
+
+// Destination-specific connection state, owned by the sink's worker thread
+enum Writer {
+    InfluxHttp { agent: ureq::Agent, url: String, token: String },
+    InfluxFile { path: String },
+    Statsd { socket: Option<UdpSocket>, addr: String, prefix: String, dogstatsd: bool },
+}
+
+impl Writer {
+    fn new(kind: &SinkKind) -> Self {
+        match kind.clone() {
+            SinkKind::InfluxHttp { url, token } => Writer::InfluxHttp {
+                agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(5)).build(),
+                url,
+                token,
+            },
+            SinkKind::InfluxFile { path } => Writer::InfluxFile { path },
+            SinkKind::Statsd { addr, prefix, dogstatsd } => Writer::Statsd { socket: None, addr, prefix, dogstatsd },
+        }
+    }
+
+    fn send(&mut self, points: &[&MetricPoint]) -> Result<(), String> {
+        match self {
+            Writer::InfluxHttp { agent, url, token } => {
+                let mut request = agent.post(url).set("Content-Type", "text/plain; charset=utf-8");
+                if !token.is_empty() {
+                    request = request.set("Authorization", &format!("Token {}", token));
+                }
+                request
+                    .send_string(&to_line_protocol(points))
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+            Writer::InfluxFile { path } => {
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&*path)
+                    .map_err(|e| e.to_string())?;
+                file.write_all(to_line_protocol(points).as_bytes()).map_err(|e| e.to_string())
+            }
+            Writer::Statsd { socket, addr, prefix, dogstatsd } => {
+                if socket.is_none() {
+                    *socket = Some(UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?);
+                }
+                let socket = socket.as_ref().expect("socket bound above");
+
+                // Pack as many lines per datagram as fit
+                let mut datagram = String::new();
+                for line in to_statsd_lines(points, prefix, *dogstatsd) {
+                    if !datagram.is_empty() && datagram.len() + line.len() + 1 > MAX_DATAGRAM_BYTES {
+                        socket.send_to(datagram.as_bytes(), &*addr).map_err(|e| e.to_string())?;
+                        datagram.clear();
+                    }
+                    if !datagram.is_empty() {
+                        datagram.push('\n');
+                    }
+                    datagram.push_str(&line);
+                }
+                if !datagram.is_empty() {
+                    socket.send_to(datagram.as_bytes(), &*addr).map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SinkStatus {
+    pub sent_samples: u64,
+    pub dropped_samples: u64,
+    pub pending_samples: usize,
+    pub failures: u64,
+    pub last_error: Option<String>,
+}
+
+// A running sink: samples go through a bounded channel to a worker thread
+pub struct Sink {
+    pub config: SinkConfig,
+    sender: mpsc::SyncSender<Vec<MetricPoint>>,
+    status: Arc<Mutex<SinkStatus>>,
+}
+
+impl Sink {
+    pub fn start(config: SinkConfig) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(MAX_BUFFERED_SAMPLES);
+        let status = Arc::new(Mutex::new(SinkStatus::default()));
+
+        {
+            let config = config.clone();
+            let status = Arc::clone(&status);
+            // The thread ends by itself once the sink is dropped and the channel closes
+            std::thread::Builder::new()
+                .name("metrics-sink".to_string())
+                .spawn(move || run_worker(config, receiver, status))?;
+        }
+
+        Ok(Self { config, sender, status })
+    }
+
+    // Never blocks; while the worker is stuck retrying and the channel is full, samples are dropped
+    pub fn push(&self, points: Vec<MetricPoint>) {
+        if let Err(mpsc::TrySendError::Full(_)) = self.sender.try_send(points) {
+            if let Ok(mut status) = self.status.lock() {
+                status.dropped_samples += 1;
+            }
+        }
+    }
+
+    pub fn status(&self) -> SinkStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+fn send_with_retry(writer: &mut Writer, points: &[&MetricPoint], max_retries: u32) -> Result<(), String> {
+    let mut attempt = 0;
+    loop {
+        match writer.send(points) {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt >= max_retries => return Err(e),
+            Err(_) => {
+                std::thread::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn run_worker(config: SinkConfig, receiver: mpsc::Receiver<Vec<MetricPoint>>, status: Arc<Mutex<SinkStatus>>) {
+    let mut writer = Writer::new(&config.kind);
+    let mut pending: VecDeque<Vec<MetricPoint>> = VecDeque::new();
+    let batch_size = config.batch_size.max(1);
+
+    for sample in receiver.iter() {
+        pending.push_back(sample);
+        // Take everything that queued up during the previous send so the cap applies to it
+        pending.extend(receiver.try_iter());
+        let mut dropped = 0;
+        while pending.len() > MAX_BUFFERED_SAMPLES {
+            pending.pop_front();
+            dropped += 1;
+        }
+
+        let result = if pending.len() >= batch_size {
+            let batch: Vec<&MetricPoint> = pending.iter().flatten().collect();
+            Some(send_with_retry(&mut writer, &batch, config.max_retries))
+        } else {
+            None
+        };
+
+        let Ok(mut status) = status.lock() else {
+            return;
+        };
+        status.dropped_samples += dropped;
+        match result {
+            Some(Ok(())) => {
+                status.sent_samples += pending.len() as u64;
+                status.last_error = None;
+                pending.clear();
+            }
+            // Failed samples stay queued and go out with the next batch
+            Some(Err(e)) => {
+                status.failures += 1;
+                status.last_error = Some(e);
+            }
+            None => {}
+        }
+        status.pending_samples = pending.len();
+    }
+}
+
+impl ResourceMonitor {
+    pub(crate) fn start_sinks(&mut self) {
+        self.sinks.clear();
+        for config in self.sink_configs.iter().filter(|c| c.enabled) {
+            match Sink::start(config.clone()) {
+                Ok(sink) => self.sinks.push(sink),
+                Err(e) => eprintln!("Error starting {} sink: {}", config.kind.label(), e),
+            }
+        }
+    }
+
+    fn apply_sinks(&mut self) {
+        self.start_sinks();
+        self.sinks_message = Some(match storage::save_json(SINKS_FILE, &self.sink_configs) {
+            Ok(()) => ("Sinks applied and saved".to_string(), true),
+            Err(e) => (format!("Sinks applied but not saved: {}", e), false),
+        });
+    }
+
+    pub(crate) fn push_to_sinks(&self, snapshot: &MetricsSnapshot) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let points = points(snapshot);
+        for sink in &self.sinks {
+            sink.push(points.clone());
+        }
+    }
+
+    pub(crate) fn render_sinks_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_sinks_window;
+        let mut apply = false;
+
+        egui::Window::new("Metric sinks")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Sinks receive every sample, using the update interval")
+                    .color(ui.visuals().weak_text_color()));
+                ui.separator();
+
+                let mut remove = None;
+                for (index, config) in self.sink_configs.iter_mut().enumerate() {
+                    ui.push_id(index, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut config.enabled, egui::RichText::new(config.kind.label()).strong());
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.small_button("Remove").clicked() {
+                                    remove = Some(index);
+                                }
+                            });
+                        });
+
+                        egui::Grid::new("sink_grid")
+                            .num_columns(2)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                match &mut config.kind {
+                                    SinkKind::InfluxHttp { url, token } => {
+                                        ui.label("Write URL: ");
+                                        ui.add(egui::TextEdit::singleline(url).desired_width(360.0));
+                                        ui.end_row();
+                                        ui.label("Token: ");
+                                        ui.add(egui::TextEdit::singleline(token).password(true).desired_width(360.0));
+                                        ui.end_row();
+                                    }
+                                    SinkKind::InfluxFile { path } => {
+                                        ui.label("File: ");
+                                        ui.add(egui::TextEdit::singleline(path).desired_width(360.0));
+                                        ui.end_row();
+                                    }
+                                    SinkKind::Statsd { addr, prefix, dogstatsd } => {
+                                        ui.label("Address: ");
+                                        ui.add(egui::TextEdit::singleline(addr).desired_width(200.0));
+                                        ui.end_row();
+                                        ui.label("Prefix: ");
+                                        ui.add(egui::TextEdit::singleline(prefix).desired_width(200.0));
+                                        ui.end_row();
+                                        ui.label("Tags: ");
+                                        ui.checkbox(dogstatsd, "DogStatsD tags");
+                                        ui.end_row();
+                                    }
+                                }
+                                ui.label("Batch: ");
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(&mut config.batch_size)
+                                        .clamp_range(1..=600)
+                                        .suffix(" samples"));
+                                    ui.label("Retries: ");
+                                    ui.add(egui::DragValue::new(&mut config.max_retries).clamp_range(0..=10));
+                                });
+                                ui.end_row();
+                            });
+
+                        // Status of the running sink with the same settings
+                        if let Some(sink) = self.sinks.iter().find(|s| s.config == *config) {
+                            let status = sink.status();
+                            ui.label(egui::RichText::new(format!(
+                                "Sent: {}  Pending: {}  Dropped: {}  Failures: {}",
+                                status.sent_samples, status.pending_samples,
+                                status.dropped_samples, status.failures))
+                                .color(ui.visuals().weak_text_color()));
+                            if let Some(error) = status.last_error {
+                                ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+                            }
+                        }
+                    });
+                    ui.separator();
+                }
+                if let Some(index) = remove {
+                    self.sink_configs.remove(index);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Add: ");
+                    if ui.button("InfluxDB (HTTP)").clicked() {
+                        self.sink_configs.push(SinkConfig::new(SinkKind::InfluxHttp {
+                            url: "http://localhost:8086/api/v2/write?org=my-org&bucket=syswatch&precision=ns".to_string(),
+                            token: String::new(),
+                        }));
+                    }
+                    if ui.button("InfluxDB (file)").clicked() {
+                        self.sink_configs.push(SinkConfig::new(SinkKind::InfluxFile {
+                            path: "syswatch_metrics.lp".to_string(),
+                        }));
+                    }
+                    if ui.button("StatsD").clicked() {
+                        self.sink_configs.push(SinkConfig::new(SinkKind::Statsd {
+                            addr: "127.0.0.1:8125".to_string(),
+                            prefix: "syswatch".to_string(),
+                            dogstatsd: false,
+                        }));
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        apply = true;
+                    }
+                    if let Some((message, success)) = &self.sinks_message {
+                        let color = if *success { color(self.palette.good) } else { ui.visuals().error_fg_color };
+                        ui.label(egui::RichText::new(message).color(color));
+                    }
+                });
+            });
+
+        self.show_sinks_window = open;
+        if apply {
+            self.apply_sinks();
+        }
+        if self.show_sinks_window && !self.sinks.is_empty() {
+            // Keep the counters moving while the window is open
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(measurement: &'static str, tags: &[(&'static str, &str)], fields: &[(&'static str, f64)]) -> MetricPoint {
+        MetricPoint {
+            measurement,
+            tags: tags.iter().map(|&(key, value)| (key, value.to_string())).collect(),
+            fields: fields.to_vec(),
+            timestamp_ns: 1_700_000_000_123_000_000,
+        }
+    }
+
+    #[test]
+    fn line_protocol() {
+        let system = point("system", &[("host", "box")], &[("cpu_usage_percent", 12.5), ("process_count", 301.0)]);
+        let disk = point("disk", &[("host", "box"), ("device", "/dev/sda1"), ("type", "")], &[("used_bytes", 1e12)]);
+        assert_eq!(
+            to_line_protocol(&[&system, &disk]),
+            "syswatch_system,host=box cpu_usage_percent=12.5,process_count=301 1700000000123000000\n\
+             syswatch_disk,host=box,device=/dev/sda1 used_bytes=1000000000000 1700000000123000000\n"
+        );
+    }
+
+    #[test]
+    fn line_protocol_escapes_separators() {
+        let process = point(
+            "process",
+            &[("host", "my box"), ("name", "a,b=c\\d"), ("we ird", "x")],
+            &[("cpu,usage=", 1.0)],
+        );
+        assert_eq!(
+            to_line_protocol(&[&process]),
+            "syswatch_process,host=my\\ box,name=a\\,b\\=c\\\\d,we\\ ird=x cpu\\,usage\\==1 1700000000123000000\n"
+        );
+        assert_eq!(escape_line_protocol("disk io,x=1", &[]), "disk\\ io\\,x=1");
+    }
+
+    #[test]
+    fn statsd_puts_tags_in_the_name() {
+        let process = point("process", &[("host", "box"), ("pid", "42"), ("name", "Web Content"), ("user", "")],
+            &[("cpu_usage_percent", 3.25), ("memory_bytes", 2048.0)]);
+        assert_eq!(to_statsd_lines(&[&process], "syswatch", false), [
+            "syswatch.process.42.Web_Content.cpu_usage_percent:3.25|g",
+            "syswatch.process.42.Web_Content.memory_bytes:2048|g",
+        ]);
+    }
+
+    #[test]
+    fn dogstatsd_tags() {
+        let cpu = point("cpu", &[("host", "box.local"), ("core", "3")], &[("usage_percent", 50.0)]);
+        let process = point("process", &[("host", "box"), ("name", "a|b:c#d"), ("user", "")], &[("memory_bytes", 1.0)]);
+        assert_eq!(to_statsd_lines(&[&cpu, &process], "app.sys", true), [
+            "app.sys.cpu.usage_percent:50|g|#host:box_local,core:3",
+            "app.sys.process.memory_bytes:1|g|#host:box,name:a_b_c_d",
+        ]);
+    }
+
+    #[test]
+    fn statsd_without_prefix_or_tags() {
+        let system = point("system", &[], &[("uptime_seconds", 10.0)]);
+        assert_eq!(to_statsd_lines(&[&system], "", true), ["system.uptime_seconds:10|g"]);
+        assert_eq!(to_statsd_lines(&[&system], "", false), ["system.uptime_seconds:10|g"]);
+    }
+
+    #[test]
+    fn non_finite_values_are_skipped() {
+        let system = point("system", &[("host", "box")], &[("cpu_usage_percent", f64::NAN), ("load", 1.5)]);
+        let empty = point("gpu", &[("host", "box")], &[("usage_percent", f64::INFINITY), ("temp", f64::NEG_INFINITY)]);
+        assert_eq!(to_line_protocol(&[&system, &empty]), "syswatch_system,host=box load=1.5 1700000000123000000\n");
+        assert_eq!(to_statsd_lines(&[&system, &empty], "", false), ["system.load:1.5|g"]);
+    }
+
+    fn sink_config(path: &str) -> SinkConfig {
+        SinkConfig { max_retries: 0, ..SinkConfig::new(SinkKind::InfluxFile { path: path.to_string() }) }
+    }
+
+    #[test]
+    fn push_drops_samples_once_the_queue_is_full() {
+        let (sender, _receiver) = mpsc::sync_channel(MAX_BUFFERED_SAMPLES);
+        let sink = Sink { config: sink_config(""), sender, status: Arc::default() };
+        for _ in 0..MAX_BUFFERED_SAMPLES + 5 {
+            sink.push(Vec::new());
+        }
+        assert_eq!(sink.status().dropped_samples, 5);
+    }
+
+    #[test]
+    fn worker_caps_samples_queued_during_a_failing_send() {
+        let (sender, receiver) = mpsc::sync_channel(2 * MAX_BUFFERED_SAMPLES);
+        for _ in 0..MAX_BUFFERED_SAMPLES + 500 {
+            sender.send(vec![point("system", &[], &[("load", 1.0)])]).unwrap();
+        }
+        drop(sender);
+
+        let missing = std::env::temp_dir().join("syswatch_missing_dir").join("metrics.lp");
+        let status = Arc::default();
+        run_worker(sink_config(&missing.to_string_lossy()), receiver, Arc::clone(&status));
+
+        let status = status.lock().unwrap();
+        assert_eq!(status.pending_samples, MAX_BUFFERED_SAMPLES);
+        assert_eq!(status.dropped_samples, 500);
+        assert_eq!(status.failures, 1);
+        assert_eq!(status.sent_samples, 0);
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Local};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::ProcessInfo;
+
+// Process list loaded from a file produced by the export window
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub taken_at: Option<DateTime<Local>>,
+    pub processes: Vec<ProcessInfo>,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+        let processes = if is_csv {
+            let mut reader = csv::Reader::from_path(path)?;
+            reader
+                .deserialize::<ProcessInfo>()
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            let contents = std::fs::read_to_string(path)?;
+            serde_json::from_str::<Vec<ProcessInfo>>(&contents)?
+        };
+
+        let taken_at = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Local>::from);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            taken_at,
+            processes,
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+}
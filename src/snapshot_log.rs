@@ -0,0 +1,545 @@
+use chrono::{Local, NaiveDateTime};
+use eframe::egui;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::theme::color;
+use crate::{format_timestamp, storage, DiskInfo, ProcessInfo, ResourceMonitor, SystemTotals};
+
+// File in the config directory holding the log settings
+pub(crate) const SNAPSHOT_LOG_FILE: &str = "snapshot_log.json";
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SnapshotLogConfig {
+    pub enabled: bool,
+    pub path: String,
+    // Rotate once the file grows past this size, 0 disables
+    pub max_size_mb: f32,
+    // Rotate once the file is this old, 0 disables
+    pub rotate_every_hours: f32,
+    // Rotated files kept next to the log, oldest are deleted
+    pub keep_files: usize,
+    pub compress: bool,
+}
+
+impl Default for SnapshotLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "syswatch_snapshots.ndjson".to_string(),
+            max_size_mb: 50.0,
+            rotate_every_hours: 24.0,
+            keep_files: 10,
+            compress: true,
+        }
+    }
+}
+
+// One line of the log
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    timestamp: f64,
+    time: String,
+    system: &'a SystemTotals,
+    disks: &'a [DiskInfo],
+    processes: Vec<&'a ProcessInfo>,
+}
+
+// Append-only NDJSON writer with size and age based rotation
+pub struct SnapshotLog {
+    config: SnapshotLogConfig,
+    writer: BufWriter<File>,
+    size: u64,
+    // When the current file was started, kept across restarts so reopening doesn't reset the age
+    opened_at: SystemTime,
+}
+
+impl SnapshotLog {
+    pub fn open(config: SnapshotLogConfig) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        let metadata = file.metadata()?;
+        let size = metadata.len();
+        let opened_at = if size > 0 {
+            metadata.created().or_else(|_| metadata.modified()).unwrap_or_else(|_| SystemTime::now())
+        } else {
+            SystemTime::now()
+        };
+        Ok(Self {
+            config,
+            writer: BufWriter::new(file),
+            size,
+            opened_at,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn append(&mut self, entry: &LogEntry) -> std::io::Result<()> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        // Flush every line so a crash loses at most the current sample
+        self.writer.flush()?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn should_rotate(&self) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let too_big = self.config.max_size_mb > 0.0
+            && self.size as f64 >= self.config.max_size_mb as f64 * 1024.0 * 1024.0;
+        let too_old = self.config.rotate_every_hours > 0.0
+            && self.opened_at.elapsed().unwrap_or_default() >= Duration::from_secs_f32(self.config.rotate_every_hours * 3600.0);
+        too_big || too_old
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        let path = PathBuf::from(&self.config.path);
+        let rotated = rotated_path(&path);
+        std::fs::rename(&path, &rotated)?;
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+        self.opened_at = SystemTime::now();
+
+        // Compressing a large file takes a while, so it happens off the UI thread
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            if config.compress {
+                if let Err(e) = compress_file(&rotated) {
+                    eprintln!("Error compressing {}: {}", rotated.display(), e);
+                }
+            }
+            if let Err(e) = prune_rotated(&path, config.keep_files) {
+                eprintln!("Error removing old snapshot logs: {}", e);
+            }
+        });
+        Ok(())
+    }
+}
+
+// syswatch.ndjson -> syswatch.20240101_120000.ndjson
+fn rotated_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    let stamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+
+    // Two rotations within one second must not overwrite each other
+    let mut suffix = String::new();
+    for attempt in 1.. {
+        let candidate = path.with_file_name(format!("{}.{}{}{}", stem, stamp, suffix, extension));
+        let mut compressed = candidate.as_os_str().to_owned();
+        compressed.push(".gz");
+        if !candidate.exists() && !Path::new(&compressed).exists() {
+            return candidate;
+        }
+        suffix = format!("_{}", attempt);
+    }
+    unreachable!()
+}
+
+fn compress_file(path: &Path) -> std::io::Result<()> {
+    let mut gz_name = path.as_os_str().to_owned();
+    gz_name.push(".gz");
+
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(&gz_name)?, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(path)
+}
+
+// Time and collision suffix of a name produced by rotated_path: <stem>.YYYYMMDD_HHMMSS[_n].<ext>[.gz]
+fn rotated_stamp(name: &str, stem: &str, extension: &str) -> Option<(NaiveDateTime, u32)> {
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    let middle = name.strip_prefix(stem)?.strip_prefix('.')?.strip_suffix(extension)?;
+    let time = NaiveDateTime::parse_from_str(middle.get(..15)?, "%Y%m%d_%H%M%S").ok()?;
+    let suffix = match &middle[15..] {
+        "" => 0,
+        rest => rest.strip_prefix('_')?.parse().ok()?,
+    };
+    Some((time, suffix))
+}
+
+// Deletes the oldest rotated files beyond `keep`
+fn prune_rotated(path: &Path, keep: usize) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+
+    let mut rotated: Vec<((NaiveDateTime, u32), PathBuf)> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter_map(|p| {
+            let name = p.file_name()?.to_string_lossy().into_owned();
+            Some((rotated_stamp(&name, &stem, &extension)?, p))
+        })
+        .collect();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(keep);
+    for (_, old) in rotated.into_iter().take(excess) {
+        std::fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+impl ResourceMonitor {
+    pub(crate) fn start_snapshot_log(&mut self) {
+        self.snapshot_log = None;
+        self.recording_until = None;
+        self.snapshot_log_error = None;
+        if !self.snapshot_log_config.enabled {
+            return;
+        }
+        match SnapshotLog::open(self.snapshot_log_config.clone()) {
+            Ok(log) => self.snapshot_log = Some(log),
+            Err(e) => {
+                eprintln!("Error opening snapshot log {}: {}", self.snapshot_log_config.path, e);
+                self.snapshot_log_error = Some(format!("Could not open {}: {}", self.snapshot_log_config.path, e));
+            }
+        }
+    }
+
+    // Logs for a limited time even if the log is off; extends a recording already running
+    pub(crate) fn start_recording(&mut self, minutes: f32) -> Result<String, String> {
+        if self.snapshot_log.is_some() && self.recording_until.is_none() {
+            return Ok("snapshot log already running".to_string());
+        }
+        if self.snapshot_log.is_none() {
+            let config = SnapshotLogConfig { enabled: true, ..self.snapshot_log_config.clone() };
+            let log = SnapshotLog::open(config).map_err(|e| format!("could not open {}: {}", self.snapshot_log_config.path, e))?;
+            self.snapshot_log = Some(log);
+        }
+        self.recording_until = Some(Instant::now() + Duration::from_secs_f32(minutes.max(0.0) * 60.0));
+        Ok(format!("recording to {} for {} min", self.snapshot_log_config.path, minutes))
+    }
+
+    pub(crate) fn stop_expired_recording(&mut self) {
+        if self.recording_until.is_some_and(|until| Instant::now() >= until) {
+            self.recording_until = None;
+            if !self.snapshot_log_config.enabled {
+                self.snapshot_log = None;
+            }
+        }
+    }
+
+    // Logs the live processes the search matches, in table order, even while a snapshot file is open
+    pub(crate) fn append_snapshot_log(&mut self, live_processes: &[ProcessInfo]) {
+        if self.snapshot_log.is_none() {
+            return;
+        }
+
+        let system = self.system_totals();
+        let mut processes: Vec<&ProcessInfo> = live_processes.iter().filter(|p| self.matches_filter(p)).collect();
+        processes.sort_by(|a, b| self.compare_rows(a, b));
+        let entry = LogEntry {
+            timestamp: system.timestamp,
+            time: format_timestamp(system.timestamp, "%Y-%m-%dT%H:%M:%S%.3f%:z"),
+            system: &system,
+            disks: &self.disks,
+            processes,
+        };
+
+        let result = self.snapshot_log.as_mut().map(|log| log.append(&entry));
+        if let Some(Err(e)) = result {
+            eprintln!("Error writing snapshot log: {}", e);
+            self.snapshot_log_error = Some(format!("Error writing snapshot log: {}", e));
+        }
+    }
+
+    pub(crate) fn render_snapshot_log_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_snapshot_log_window;
+        let mut apply = false;
+
+        egui::Window::new("Snapshot log")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Appends one JSON line per update: system totals, disks and filtered processes")
+                    .color(ui.visuals().weak_text_color()));
+                ui.separator();
+
+                let config = &mut self.snapshot_log_config;
+                egui::Grid::new("snapshot_log_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Enabled: ");
+                        ui.checkbox(&mut config.enabled, "");
+                        ui.end_row();
+
+                        ui.label("File: ");
+                        ui.add(egui::TextEdit::singleline(&mut config.path).desired_width(300.0));
+                        ui.end_row();
+
+                        ui.label("Rotate at size: ");
+                        ui.add(egui::DragValue::new(&mut config.max_size_mb)
+                            .clamp_range(0.0..=10_000.0)
+                            .suffix(" MB"))
+                            .on_hover_text("0 disables size based rotation");
+                        ui.end_row();
+
+                        ui.label("Rotate every: ");
+                        ui.add(egui::DragValue::new(&mut config.rotate_every_hours)
+                            .clamp_range(0.0..=24.0 * 365.0)
+                            .suffix(" h"))
+                            .on_hover_text("0 disables time based rotation");
+                        ui.end_row();
+
+                        ui.label("Keep rotated files: ");
+                        ui.add(egui::DragValue::new(&mut config.keep_files).clamp_range(1..=1000));
+                        ui.end_row();
+
+                        ui.label("Compress: ");
+                        ui.checkbox(&mut config.compress, "gzip rotated files");
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Apply").clicked() {
+                        apply = true;
+                    }
+                    match (&self.snapshot_log, &self.snapshot_log_error) {
+                        (_, Some(error)) => {
+                            ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+                        }
+                        (Some(log), None) => {
+                            let mut text = format!("Logging, current file {:.1} MB", log.size() as f64 / 1024.0 / 1024.0);
+                            if let Some(until) = self.recording_until {
+                                let remaining = until.saturating_duration_since(Instant::now()).as_secs();
+                                text.push_str(&format!(", recording for another {}m {}s", remaining / 60, remaining % 60));
+                            }
+                            ui.label(egui::RichText::new(text).color(color(self.palette.good)));
+                        }
+                        (None, None) => {
+                            ui.label(egui::RichText::new("Not logging").color(ui.visuals().weak_text_color()));
+                        }
+                    }
+                });
+            });
+
+        self.show_snapshot_log_window = open;
+        if apply {
+            self.start_snapshot_log();
+            if let Err(e) = storage::save_json(SNAPSHOT_LOG_FILE, &self.snapshot_log_config) {
+                eprintln!("Error saving snapshot log settings: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syswatch_log_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(path: &Path) -> SnapshotLogConfig {
+        SnapshotLogConfig {
+            enabled: true,
+            path: path.to_string_lossy().into_owned(),
+            max_size_mb: 0.0,
+            rotate_every_hours: 0.0,
+            keep_files: 100,
+            compress: false,
+        }
+    }
+
+    fn write_line(log: &mut SnapshotLog) {
+        let system = SystemTotals {
+            timestamp: 1_700_000_000.0,
+            cpu_usage: 12.5,
+            total_memory: 8 << 30,
+            used_memory: 4 << 30,
+            free_memory: 4 << 30,
+            total_swap: 0,
+            used_swap: 0,
+            process_count: 1,
+            uptime: 60,
+        };
+        let process = ProcessInfo { name: "init".to_string(), pid: 1, ..Default::default() };
+        let entry = LogEntry {
+            timestamp: system.timestamp,
+            time: String::new(),
+            system: &system,
+            disks: &[],
+            processes: vec![&process],
+        };
+        log.append(&entry).unwrap();
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn rotated_names(dir: &Path) -> Vec<String> {
+        names(dir).into_iter().filter(|name| rotated_stamp(name, "syswatch", ".ndjson").is_some()).collect()
+    }
+
+    #[test]
+    fn rotated_names_carry_a_timestamp_and_never_collide() {
+        let dir = temp_dir("names");
+        let path = dir.join("syswatch.ndjson");
+
+        let first = rotated_path(&path);
+        std::fs::write(&first, "").unwrap();
+        let second = rotated_path(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let stamp = |p: &Path| rotated_stamp(&p.file_name().unwrap().to_string_lossy(), "syswatch", ".ndjson").unwrap();
+        assert_eq!(first.parent(), Some(dir.as_path()));
+        assert_eq!(stamp(&first).1, 0);
+        assert!(stamp(&second) > stamp(&first));
+        if stamp(&second).0 == stamp(&first).0 {
+            assert_eq!(stamp(&second).1, 1);
+        }
+    }
+
+    #[test]
+    fn only_rotated_names_are_recognized() {
+        let time = NaiveDateTime::parse_from_str("20240101_120000", "%Y%m%d_%H%M%S").unwrap();
+        assert_eq!(rotated_stamp("syswatch.20240101_120000.ndjson", "syswatch", ".ndjson"), Some((time, 0)));
+        assert_eq!(rotated_stamp("syswatch.20240101_120000_12.ndjson.gz", "syswatch", ".ndjson"), Some((time, 12)));
+        assert_eq!(rotated_stamp("syswatch.20240101_120000", "syswatch", ""), Some((time, 0)));
+
+        for name in [
+            "syswatch.ndjson",
+            "syswatch.toml",
+            "syswatch.json",
+            "syswatch.bak",
+            "syswatch.20240101.ndjson",
+            "syswatch.20241301_120000.ndjson",
+            "syswatch.20240101_120000_x.ndjson",
+            "syswatch.20240101_120000.json",
+            "other.20240101_120000.ndjson",
+        ] {
+            assert_eq!(rotated_stamp(name, "syswatch", ".ndjson"), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn rotates_when_the_file_grows_past_the_limit() {
+        let dir = temp_dir("size");
+        let path = dir.join("syswatch.ndjson");
+        // About 100 bytes, less than one line
+        let mut log = SnapshotLog::open(SnapshotLogConfig { max_size_mb: 0.0001, ..config(&path) }).unwrap();
+
+        write_line(&mut log);
+        assert!(rotated_names(&dir).is_empty());
+        let line_size = log.size();
+        write_line(&mut log);
+        assert_eq!(log.size(), line_size);
+        assert_eq!(rotated_names(&dir).len(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotates_by_age_including_time_before_a_restart() {
+        let dir = temp_dir("age");
+        let path = dir.join("syswatch.ndjson");
+        let config = SnapshotLogConfig { rotate_every_hours: 1.0, ..config(&path) };
+
+        let mut log = SnapshotLog::open(config.clone()).unwrap();
+        assert!(!log.should_rotate());
+        write_line(&mut log);
+        assert!(!log.should_rotate());
+        drop(log);
+
+        // Reopening takes the age from the file, not from the new session
+        let mut log = SnapshotLog::open(config).unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert!(log.opened_at == metadata.created().or_else(|_| metadata.modified()).unwrap());
+
+        log.opened_at = SystemTime::now() - Duration::from_secs(2 * 3600);
+        assert!(log.should_rotate());
+        write_line(&mut log);
+        assert!(!log.should_rotate());
+        assert_eq!(rotated_names(&dir).len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compressed_files_replace_the_original() {
+        let dir = temp_dir("gzip");
+        let rotated = dir.join("syswatch.20240101_120000.ndjson");
+        std::fs::write(&rotated, "{\"a\":1}\n{\"a\":2}\n").unwrap();
+
+        compress_file(&rotated).unwrap();
+        let mut text = String::new();
+        GzDecoder::new(File::open(dir.join("syswatch.20240101_120000.ndjson.gz")).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        let remaining = names(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(text, "{\"a\":1}\n{\"a\":2}\n");
+        assert_eq!(remaining, ["syswatch.20240101_120000.ndjson.gz"]);
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_rotated_files_only() {
+        let dir = temp_dir("prune");
+        let files = [
+            "syswatch.ndjson",
+            "syswatch.toml",
+            "syswatch.json",
+            "syswatch.bak",
+            "syswatch.20240101.ndjson",
+            "syswatch.20231231_235959.ndjson.gz",
+            "syswatch.20240101_120000.ndjson.gz",
+            "syswatch.20240101_120000_2.ndjson.gz",
+            "syswatch.20240101_120000_10.ndjson",
+            "syswatch.20240102_000000.ndjson",
+        ];
+        for name in files {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        prune_rotated(&dir.join("syswatch.ndjson"), 2).unwrap();
+        let remaining = names(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // "_10" is newer than "_2" although it sorts before it as text
+        assert_eq!(remaining, [
+            "syswatch.20240101.ndjson",
+            "syswatch.20240101_120000_10.ndjson",
+            "syswatch.20240102_000000.ndjson",
+            "syswatch.bak",
+            "syswatch.json",
+            "syswatch.ndjson",
+            "syswatch.toml",
+        ]);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+
+// Per-user directory holding SysWatch's saved files
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("SysWatch"))
+}
+
+// Reads a JSON file from the config directory; a missing or broken file yields None
+pub fn load_json<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let path = config_dir()?.join(file_name);
+    let contents = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Ignoring invalid {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn save_json<T: Serialize>(file_name: &str, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = config_dir().ok_or("no configuration directory on this system")?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(file_name), serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
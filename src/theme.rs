@@ -0,0 +1,336 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::ResourceMonitor;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    // Light or dark, whichever the OS uses; dark when it doesn't say
+    #[default]
+    System,
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl ThemeMode {
+    const ALL: [ThemeMode; 4] = [ThemeMode::System, ThemeMode::Dark, ThemeMode::Light, ThemeMode::HighContrast];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ThemeMode::System => "Follow system",
+            ThemeMode::Dark => "Dark",
+            ThemeMode::Light => "Light",
+            ThemeMode::HighContrast => "High contrast",
+        }
+    }
+}
+
+// Colors the table, progress bars and charts are drawn with; written as "#rrggbb" in the files
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Palette {
+    #[serde(with = "hex_color")]
+    pub text: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub weak_text: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub row_even: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub row_odd: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub hover: [u8; 3],
+    // Selection, progress bars and chart markers
+    #[serde(with = "hex_color")]
+    pub accent: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub good: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub warning: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub critical: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub chart_cpu: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub chart_memory: [u8; 3],
+    // Background of the offline snapshot banner
+    #[serde(with = "hex_color")]
+    pub banner: [u8; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Palette {
+    pub fn dark() -> Self {
+        Self {
+            text: [220, 220, 220],
+            weak_text: [150, 150, 150],
+            row_even: [30, 30, 30],
+            row_odd: [40, 40, 40],
+            hover: [100, 100, 100],
+            accent: [0, 120, 215],
+            good: [100, 200, 100],
+            warning: [255, 200, 100],
+            critical: [255, 100, 100],
+            chart_cpu: [255, 100, 100],
+            chart_memory: [100, 150, 255],
+            banner: [90, 60, 0],
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            text: [30, 30, 30],
+            weak_text: [110, 110, 110],
+            row_even: [250, 250, 250],
+            row_odd: [236, 236, 236],
+            hover: [160, 160, 160],
+            accent: [0, 100, 190],
+            good: [30, 130, 50],
+            warning: [170, 110, 0],
+            critical: [200, 40, 40],
+            chart_cpu: [210, 50, 50],
+            chart_memory: [40, 90, 200],
+            banner: [255, 230, 170],
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            text: [255, 255, 255],
+            weak_text: [200, 200, 200],
+            row_even: [0, 0, 0],
+            row_odd: [24, 24, 24],
+            hover: [255, 255, 255],
+            accent: [0, 170, 255],
+            good: [0, 255, 0],
+            warning: [255, 255, 0],
+            critical: [255, 80, 80],
+            chart_cpu: [255, 255, 0],
+            chart_memory: [0, 255, 255],
+            banner: [80, 60, 0],
+        }
+    }
+
+    // Okabe-Ito colors, which stay apart for the common forms of color blindness
+    fn colorblind_safe(self, dark: bool) -> Self {
+        Self {
+            good: [0, 158, 115],
+            warning: [230, 159, 0],
+            critical: [213, 94, 0],
+            chart_cpu: [230, 159, 0],
+            chart_memory: if dark { [86, 180, 233] } else { [0, 114, 178] },
+            accent: if dark { [86, 180, 233] } else { [0, 114, 178] },
+            ..self
+        }
+    }
+}
+
+mod hex_color {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(rgb: &[u8; 3], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 3], D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let hex = text.strip_prefix('#').unwrap_or(&text);
+        // from_str_radix alone would also take a sign, as in "+12345"
+        let valid = hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit());
+        let value = valid.then(|| u32::from_str_radix(hex, 16).ok()).flatten()
+            .ok_or_else(|| D::Error::custom(format!("\"{}\" is not a #rrggbb color", text)))?;
+        Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+    }
+}
+
+pub fn color(rgb: [u8; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2])
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub mode: ThemeMode,
+    pub colorblind_safe: bool,
+    // Use `palette` instead of the built-in colors of the mode
+    pub custom_palette: bool,
+    pub palette: Palette,
+}
+
+impl ThemeSettings {
+    // Picks the mode to draw with; `system` is what the OS reports, if anything
+    fn resolve(&self, system: Option<eframe::Theme>) -> ThemeMode {
+        match (self.mode, system) {
+            (ThemeMode::System, Some(eframe::Theme::Light)) => ThemeMode::Light,
+            (ThemeMode::System, _) => ThemeMode::Dark,
+            (mode, _) => mode,
+        }
+    }
+
+    fn builtin_palette(&self, mode: ThemeMode) -> Palette {
+        let palette = match mode {
+            ThemeMode::Light => Palette::light(),
+            ThemeMode::HighContrast => Palette::high_contrast(),
+            _ => Palette::dark(),
+        };
+        if self.colorblind_safe {
+            palette.colorblind_safe(mode != ThemeMode::Light)
+        } else {
+            palette
+        }
+    }
+
+    fn visuals(&self, mode: ThemeMode, palette: &Palette) -> egui::Visuals {
+        let mut visuals = match mode {
+            ThemeMode::Light => egui::Visuals::light(),
+            _ => egui::Visuals::dark(),
+        };
+        if mode == ThemeMode::HighContrast {
+            visuals.panel_fill = egui::Color32::BLACK;
+            visuals.window_fill = egui::Color32::BLACK;
+            visuals.extreme_bg_color = egui::Color32::BLACK;
+            visuals.window_stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+            for widget in [&mut visuals.widgets.noninteractive, &mut visuals.widgets.inactive] {
+                widget.bg_stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(200));
+            }
+            visuals.widgets.hovered.bg_stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
+            visuals.widgets.active.bg_stroke = egui::Stroke::new(2.0, egui::Color32::WHITE);
+        }
+        visuals.widgets.noninteractive.fg_stroke.color = color(palette.text);
+        visuals.faint_bg_color = color(palette.row_odd);
+        visuals.selection.bg_fill = color(palette.accent);
+        visuals.hyperlink_color = color(palette.accent);
+        visuals.warn_fg_color = color(palette.warning);
+        visuals.error_fg_color = color(palette.critical);
+        visuals
+    }
+}
+
+impl ResourceMonitor {
+    // Called every frame; only touches the style when the theme or the OS setting changed.
+    // eframe swaps in its own visuals when the OS theme changes, so that is tracked too.
+    pub(crate) fn apply_theme(&mut self, ctx: &egui::Context, system: Option<eframe::Theme>) {
+        let key = (self.theme.clone(), system);
+        if self.applied_theme.as_ref() == Some(&key) {
+            return;
+        }
+        let mode = self.theme.resolve(system);
+        self.palette = if self.theme.custom_palette {
+            self.theme.palette
+        } else {
+            self.theme.builtin_palette(mode)
+        };
+        ctx.set_visuals(self.theme.visuals(mode, &self.palette));
+        self.applied_theme = Some(key);
+    }
+
+    pub(crate) fn render_theme_window(&mut self, ctx: &egui::Context, system: Option<eframe::Theme>) {
+        let mut open = self.show_theme_window;
+
+        egui::Window::new("Theme")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for mode in ThemeMode::ALL {
+                        ui.selectable_value(&mut self.theme.mode, mode, mode.label());
+                    }
+                });
+                if self.theme.mode == ThemeMode::System && system.is_none() {
+                    ui.label(egui::RichText::new("The system doesn't report a theme here, so dark is used")
+                        .color(ui.visuals().weak_text_color()));
+                }
+                ui.checkbox(&mut self.theme.colorblind_safe, "Colorblind-safe colors")
+                    .on_hover_text("Status and chart colors that stay distinguishable with red-green color blindness");
+
+                ui.separator();
+                let was_custom = self.theme.custom_palette;
+                ui.checkbox(&mut self.theme.custom_palette, "Custom palette");
+                if self.theme.custom_palette && !was_custom {
+                    // Start editing from what is on screen
+                    self.theme.palette = self.palette;
+                }
+
+                let custom = self.theme.custom_palette;
+                let palette = if custom { &mut self.theme.palette } else { &mut self.palette };
+                ui.add_enabled_ui(custom, |ui| {
+                    egui::Grid::new("palette_grid")
+                        .num_columns(4)
+                        .spacing([10.0, 6.0])
+                        .show(ui, |ui| {
+                            let entries: [(&str, &mut [u8; 3]); 12] = [
+                                ("Text", &mut palette.text),
+                                ("Dimmed text", &mut palette.weak_text),
+                                ("Even rows", &mut palette.row_even),
+                                ("Odd rows", &mut palette.row_odd),
+                                ("Hover", &mut palette.hover),
+                                ("Accent", &mut palette.accent),
+                                ("Good", &mut palette.good),
+                                ("Warning", &mut palette.warning),
+                                ("Critical", &mut palette.critical),
+                                ("CPU chart", &mut palette.chart_cpu),
+                                ("Memory chart", &mut palette.chart_memory),
+                                ("Snapshot banner", &mut palette.banner),
+                            ];
+                            for (index, (label, rgb)) in entries.into_iter().enumerate() {
+                                ui.label(label);
+                                egui::color_picker::color_edit_button_srgb(ui, rgb);
+                                if index % 2 == 1 {
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                });
+
+                ui.separator();
+                if ui.button("Restore defaults").clicked() {
+                    self.theme = ThemeSettings::default();
+                }
+            });
+
+        self.show_theme_window = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Color(#[serde(with = "hex_color")] [u8; 3]);
+
+    fn parse(text: &str) -> Result<[u8; 3], String> {
+        serde_json::from_value::<Color>(serde_json::Value::String(text.to_string()))
+            .map(|c| c.0)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn hex_colors_round_trip() {
+        assert_eq!(parse("#0072b2"), Ok([0, 114, 178]));
+        assert_eq!(parse("FFFFFF"), Ok([255, 255, 255]));
+        assert_eq!(serde_json::to_string(&Color([0, 114, 178])).unwrap(), "\"#0072b2\"");
+    }
+
+    #[test]
+    fn malformed_hex_colors_are_rejected() {
+        for text in ["#12", "zzzzzz", "#zzzzzz", "", "#", "#1234567", "+12345", "#-12345", "#12 345", "#ééé"] {
+            assert_eq!(parse(text), Err(format!("\"{}\" is not a #rrggbb color", text)), "{}", text);
+        }
+    }
+
+    #[test]
+    fn palettes_load_with_missing_and_bad_entries() {
+        let palette: Palette = toml::from_str("accent = \"#0072b2\"").unwrap();
+        assert!(palette == Palette { accent: [0, 114, 178], ..Palette::dark() });
+
+        let error = toml::from_str::<Palette>("accent = \"#12\"").err().unwrap().to_string();
+        assert!(error.contains("\"#12\" is not a #rrggbb color"), "{}", error);
+    }
+}
@@ -0,0 +1,274 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::theme::{color, Palette};
+use crate::ResourceMonitor;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryThresholdMode {
+    // Megabytes per process
+    Absolute,
+    // Share of total physical memory, so the same setting fits small and large machines
+    PercentOfRam,
+}
+
+// Values above which table and disk figures take the warning or critical color of the theme
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ColorThresholds {
+    pub cpu_warning: f32,
+    pub cpu_critical: f32,
+    pub memory_mode: MemoryThresholdMode,
+    pub memory_warning_mb: f64,
+    pub memory_critical_mb: f64,
+    pub memory_warning_percent: f64,
+    pub memory_critical_percent: f64,
+    pub disk_warning: f32,
+    pub disk_critical: f32,
+}
+
+impl Default for ColorThresholds {
+    fn default() -> Self {
+        Self {
+            cpu_warning: 20.0,
+            cpu_critical: 50.0,
+            memory_mode: MemoryThresholdMode::Absolute,
+            memory_warning_mb: 100.0,
+            memory_critical_mb: 500.0,
+            memory_warning_percent: 5.0,
+            memory_critical_percent: 20.0,
+            disk_warning: 70.0,
+            disk_critical: 90.0,
+        }
+    }
+}
+
+fn level_color(value: f64, warning: f64, critical: f64, colors: [[u8; 3]; 3]) -> egui::Color32 {
+    if value > critical {
+        color(colors[2])
+    } else if value > warning {
+        color(colors[1])
+    } else {
+        color(colors[0])
+    }
+}
+
+impl ColorThresholds {
+    pub fn cpu_color(&self, cpu_usage: f32, palette: &Palette) -> egui::Color32 {
+        level_color(cpu_usage as f64, self.cpu_warning as f64, self.cpu_critical as f64,
+            [palette.text, palette.warning, palette.critical])
+    }
+
+    pub fn memory_color(&self, memory_bytes: u64, total_memory: u64, palette: &Palette) -> egui::Color32 {
+        let (value, warning, critical) = match self.memory_mode {
+            MemoryThresholdMode::Absolute => (
+                memory_bytes as f64 / 1024.0 / 1024.0,
+                self.memory_warning_mb,
+                self.memory_critical_mb,
+            ),
+            // Multiplying first keeps exact percentages exact, so a value at the threshold doesn't cross it
+            MemoryThresholdMode::PercentOfRam if total_memory > 0 => (
+                memory_bytes as f64 * 100.0 / total_memory as f64,
+                self.memory_warning_percent,
+                self.memory_critical_percent,
+            ),
+            MemoryThresholdMode::PercentOfRam => return color(palette.text),
+        };
+        level_color(value, warning, critical, [palette.text, palette.warning, palette.critical])
+    }
+
+    pub fn disk_color(&self, usage_percent: f32, palette: &Palette) -> egui::Color32 {
+        level_color(usage_percent as f64, self.disk_warning as f64, self.disk_critical as f64,
+            [palette.good, palette.warning, palette.critical])
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let pairs = [
+            ("cpu", self.cpu_warning as f64, self.cpu_critical as f64),
+            ("memory", self.memory_warning_mb, self.memory_critical_mb),
+            ("memory percent", self.memory_warning_percent, self.memory_critical_percent),
+            ("disk", self.disk_warning as f64, self.disk_critical as f64),
+        ];
+        for (name, warning, critical) in pairs {
+            if warning < 0.0 || critical < 0.0 {
+                errors.push(format!("colors: {} thresholds must not be negative", name));
+            }
+            if warning > critical {
+                errors.push(format!("colors: {} warning threshold ({}) is above the critical one ({})", name, warning, critical));
+            }
+        }
+        if self.memory_warning_percent > 100.0 || self.memory_critical_percent > 100.0 {
+            errors.push("colors: memory percent thresholds must not exceed 100".to_string());
+        }
+        errors
+    }
+}
+
+// Warning and critical editors; critical is kept at or above warning
+fn threshold_pair<T: egui::emath::Numeric>(ui: &mut egui::Ui, warning: &mut T, critical: &mut T, max: T, suffix: &str) {
+    ui.add(egui::DragValue::new(warning).clamp_range(T::from_f64(0.0)..=max).suffix(suffix));
+    ui.add(egui::DragValue::new(critical).clamp_range(*warning..=max).suffix(suffix));
+}
+
+// A value below, between and above the thresholds, colored as the table would
+fn preview(ui: &mut egui::Ui, samples: [(String, egui::Color32); 3]) {
+    ui.horizontal(|ui| {
+        for (text, color) in samples {
+            ui.label(egui::RichText::new(text).color(color).monospace());
+        }
+    });
+}
+
+impl ResourceMonitor {
+    pub(crate) fn render_thresholds_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_thresholds_window;
+        let total_memory = self.system.total_memory();
+        let palette = self.palette;
+
+        egui::Window::new("Color thresholds")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Values above the warning threshold take the warning color, above the critical one the critical color. \
+                    Changes apply to the table right away.")
+                    .color(color(palette.weak_text)));
+                ui.separator();
+
+                let thresholds = &mut self.color_thresholds;
+                egui::Grid::new("thresholds_grid")
+                    .num_columns(4)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.label(egui::RichText::new("Warning").color(color(palette.text)));
+                        ui.label(egui::RichText::new("Critical").color(color(palette.text)));
+                        ui.label(egui::RichText::new("Preview").color(color(palette.text)));
+                        ui.end_row();
+
+                        ui.label("Process CPU: ");
+                        threshold_pair(ui, &mut thresholds.cpu_warning, &mut thresholds.cpu_critical, 10_000.0, " %");
+                        let (warning, critical) = (thresholds.cpu_warning, thresholds.cpu_critical);
+                        preview(ui, [warning / 2.0, (warning + critical) / 2.0, critical + 10.0]
+                            .map(|value| (format!("{:.1}%", value), thresholds.cpu_color(value, &palette))));
+                        ui.end_row();
+
+                        ui.label("Process memory: ");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut thresholds.memory_mode, MemoryThresholdMode::Absolute, "MB");
+                            ui.selectable_value(&mut thresholds.memory_mode, MemoryThresholdMode::PercentOfRam, "% of RAM");
+                        });
+                        ui.end_row();
+
+                        ui.label("");
+                        let samples_mb = match thresholds.memory_mode {
+                            MemoryThresholdMode::Absolute => {
+                                threshold_pair(ui, &mut thresholds.memory_warning_mb, &mut thresholds.memory_critical_mb,
+                                    1_048_576.0, " MB");
+                                let (warning, critical) = (thresholds.memory_warning_mb, thresholds.memory_critical_mb);
+                                [warning / 2.0, (warning + critical) / 2.0, critical * 1.5]
+                            }
+                            MemoryThresholdMode::PercentOfRam => {
+                                threshold_pair(ui, &mut thresholds.memory_warning_percent, &mut thresholds.memory_critical_percent,
+                                    100.0, " %");
+                                let total_mb = total_memory as f64 / 1024.0 / 1024.0;
+                                let (warning, critical) = (thresholds.memory_warning_percent, thresholds.memory_critical_percent);
+                                [warning / 2.0, (warning + critical) / 2.0, (critical + 100.0) / 2.0]
+                                    .map(|percent| total_mb * percent / 100.0)
+                            }
+                        };
+                        preview(ui, samples_mb.map(|mb| {
+                            let bytes = (mb * 1024.0 * 1024.0) as u64;
+                            (format!("{:.0} MB", mb), thresholds.memory_color(bytes, total_memory, &palette))
+                        }));
+                        ui.end_row();
+
+                        ui.label("Disk usage: ");
+                        threshold_pair(ui, &mut thresholds.disk_warning, &mut thresholds.disk_critical, 100.0, " %");
+                        let (warning, critical) = (thresholds.disk_warning, thresholds.disk_critical);
+                        preview(ui, [warning / 2.0, (warning + critical) / 2.0, (critical + 100.0) / 2.0]
+                            .map(|value| (format!("{:.0}%", value), thresholds.disk_color(value, &palette))));
+                        ui.end_row();
+                    });
+
+                if thresholds.memory_mode == MemoryThresholdMode::PercentOfRam {
+                    ui.label(egui::RichText::new(format!("Total RAM: {:.1} GB", total_memory as f64 / 1024.0 / 1024.0 / 1024.0))
+                        .color(color(palette.weak_text)));
+                }
+
+                ui.separator();
+                if ui.button("Restore defaults").clicked() {
+                    self.color_thresholds = ColorThresholds::default();
+                }
+            });
+
+        self.show_thresholds_window = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(ColorThresholds::default().validate().is_empty());
+    }
+
+    #[test]
+    fn validation_reports_each_problem() {
+        let thresholds = ColorThresholds {
+            cpu_warning: 60.0,
+            cpu_critical: 50.0,
+            memory_warning_mb: -1.0,
+            memory_warning_percent: 50.0,
+            memory_critical_percent: 150.0,
+            ..Default::default()
+        };
+        assert_eq!(thresholds.validate(), [
+            "colors: cpu warning threshold (60) is above the critical one (50)",
+            "colors: memory thresholds must not be negative",
+            "colors: memory percent thresholds must not exceed 100",
+        ]);
+
+        // Equal thresholds are allowed
+        let equal = ColorThresholds { disk_warning: 80.0, disk_critical: 80.0, ..Default::default() };
+        assert!(equal.validate().is_empty());
+    }
+
+    #[test]
+    fn percent_of_ram_colors_change_above_each_threshold() {
+        let palette = Palette::dark();
+        let thresholds = ColorThresholds {
+            memory_mode: MemoryThresholdMode::PercentOfRam,
+            memory_warning_percent: 7.0,
+            memory_critical_percent: 20.0,
+            ..Default::default()
+        };
+        let color_of = |bytes, total| thresholds.memory_color(bytes, total, &palette);
+
+        assert_eq!(color_of(0, 100), color(palette.text));
+        assert_eq!(color_of(7, 100), color(palette.text));
+        assert_eq!(color_of(8, 100), color(palette.warning));
+        assert_eq!(color_of(20, 100), color(palette.warning));
+        assert_eq!(color_of(21, 100), color(palette.critical));
+        assert_eq!(color_of(70 << 30, 1000 << 30), color(palette.text));
+        assert_eq!(color_of((70 << 30) + 1, 1000 << 30), color(palette.warning));
+        // Unknown total memory
+        assert_eq!(color_of(u64::MAX, 0), color(palette.text));
+    }
+
+    #[test]
+    fn absolute_and_disk_colors() {
+        let palette = Palette::dark();
+        let thresholds = ColorThresholds::default();
+        const MB: u64 = 1024 * 1024;
+        assert_eq!(thresholds.memory_color(100 * MB, 0, &palette), color(palette.text));
+        assert_eq!(thresholds.memory_color(100 * MB + 1, 0, &palette), color(palette.warning));
+        assert_eq!(thresholds.memory_color(501 * MB, 0, &palette), color(palette.critical));
+        assert_eq!(thresholds.disk_color(70.0, &palette), color(palette.good));
+        assert_eq!(thresholds.disk_color(90.5, &palette), color(palette.critical));
+        assert_eq!(thresholds.cpu_color(20.5, &palette), color(palette.warning));
+    }
+}
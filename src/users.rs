@@ -0,0 +1,123 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use sysinfo::{Gid, Groups, Uid, Users};
+
+use crate::theme::color;
+use crate::ResourceMonitor;
+
+// Accounts rarely change, so the lists are only re-read this often
+const NAMES_REFRESH: Duration = Duration::from_secs(60);
+
+// Login and group names by ID
+pub struct UserNames {
+    users: HashMap<Uid, String>,
+    groups: HashMap<Gid, String>,
+    loaded_at: Instant,
+}
+
+impl UserNames {
+    pub fn load() -> Self {
+        let users = Users::new_with_refreshed_list()
+            .list()
+            .iter()
+            .map(|user| (user.id().clone(), user.name().to_string()))
+            .collect();
+        let groups = Groups::new_with_refreshed_list()
+            .list()
+            .iter()
+            .map(|group| (*group.id(), group.name().to_string()))
+            .collect();
+        Self { users, groups, loaded_at: Instant::now() }
+    }
+
+    pub fn refresh_if_stale(&mut self) {
+        if self.loaded_at.elapsed() > NAMES_REFRESH {
+            *self = Self::load();
+        }
+    }
+
+    // The login name, or the numeric ID for accounts that have none (e.g. in containers)
+    pub fn user(&self, uid: Option<&Uid>) -> Option<String> {
+        uid.map(|uid| self.users.get(uid).cloned().unwrap_or_else(|| uid.to_string()))
+    }
+
+    pub fn group(&self, gid: Option<Gid>) -> Option<String> {
+        gid.map(|gid| self.groups.get(&gid).cloned().unwrap_or_else(|| gid.to_string()))
+    }
+}
+
+// One line of the Users panel
+struct UserSummary {
+    user: String,
+    processes: usize,
+    cpu_usage: f32,
+    memory_usage: u64,
+}
+
+impl ResourceMonitor {
+    // Totals per real user over every process, search or not; busiest first
+    fn user_summaries(&self) -> Vec<UserSummary> {
+        let mut by_user: HashMap<&str, UserSummary> = HashMap::new();
+        for process in &self.processes {
+            let user = process.user.as_deref().unwrap_or("N/A");
+            let summary = by_user.entry(user).or_insert_with(|| UserSummary {
+                user: user.to_string(),
+                processes: 0,
+                cpu_usage: 0.0,
+                memory_usage: 0,
+            });
+            summary.processes += 1;
+            summary.cpu_usage += process.cpu_usage;
+            summary.memory_usage += process.memory_usage;
+        }
+        let mut summaries: Vec<UserSummary> = by_user.into_values().collect();
+        summaries.sort_by(|a, b| {
+            b.cpu_usage.total_cmp(&a.cpu_usage)
+                .then(b.memory_usage.cmp(&a.memory_usage))
+                .then_with(|| a.user.cmp(&b.user))
+        });
+        summaries
+    }
+
+    pub(crate) fn render_users(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Users");
+        let summaries = self.user_summaries();
+        let mut show_user = None;
+
+        egui::ScrollArea::vertical()
+            .id_source("users_scroll")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                egui::Grid::new("users_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for title in ["User", "Processes", "CPU %", "Memory"] {
+                            ui.label(egui::RichText::new(title).strong());
+                        }
+                        ui.end_row();
+
+                        for summary in &summaries {
+                            if summary.user == "N/A" {
+                                ui.label(&summary.user);
+                            } else if ui.link(&summary.user).on_hover_text("Show only this user's processes").clicked() {
+                                show_user = Some(summary.user.clone());
+                            }
+                            ui.label(summary.processes.to_string());
+                            ui.label(egui::RichText::new(format!("{:.1}%", summary.cpu_usage))
+                                .color(self.color_thresholds.cpu_color(summary.cpu_usage, &self.palette)));
+                            ui.label(egui::RichText::new(format!("{:.1} MB", summary.memory_usage as f64 / 1024.0 / 1024.0))
+                                .color(color(self.palette.text)));
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(user) = show_user {
+            self.set_filter(format!("user=\"{}\"", user.replace('"', "\\\"")));
+        }
+    }
+}
+